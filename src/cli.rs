@@ -1,7 +1,15 @@
-use crate::{cmd, waypoints};
-use crate::TELE;
+use crate::{cmd, config, picker, term};
+use crate::format::Format;
+use crate::doctor::Search;
+use crate::shell::Shell;
+use crate::filesystem::Filesystem;
 use clap::{App, AppSettings, Arg, SubCommand};
-use waypoints::ListView;
+use std::env;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tele::import::Source;
+use tele::share::{self, Conflict};
+use tele::{Error, ListSort, ListView, Result, TELE};
 
 macro_rules! global_settings {
     () => {
@@ -9,29 +17,60 @@ macro_rules! global_settings {
             AppSettings::UnifiedHelpMessage,
             AppSettings::DeriveDisplayOrder,
             AppSettings::ColoredHelp,
-            AppSettings::ColorAlways,
+            term::clap_color(),
         ]
     };
 }
+/// Value of `--color`, read ahead of clap since it affects clap's own output
+pub fn color_arg() -> Option<String> {
+    // arguments such as paths need not be UTF-8, only `--color` is read
+    let mut args = env::args_os().skip(1).take_while(|a| a != "--");
+    while let Some(arg) = args.next() {
+        if arg == "--color" {
+            return args.next().map(|v| v.to_string_lossy().into_owned());
+        }
+        if let Some(value) = arg.to_str().and_then(|a| a.strip_prefix("--color=")) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
 pub fn parse_args() -> clap::ArgMatches<'static> {
+    app().get_matches()
+}
+
+fn app() -> App<'static, 'static> {
     App::new("tele")
         .version(crate_version!())
         .about("Quickly `cd` into commonly used directories.")
         .settings(global_settings!())
         .settings(&[
             AppSettings::GlobalVersion,
-            AppSettings::ArgRequiredElseHelp,
-            AppSettings::SubcommandsNegateReqs,
+            // waypoints resembling a subcommand would otherwise be rejected
+            // with a "did you mean" error
+            AppSettings::AllowExternalSubcommands,
             AppSettings::VersionlessSubcommands,
         ])
+        .arg(
+            Arg::with_name("color")
+                .help("When to use colors")
+                .long("color")
+                .possible_values(&["auto", "always", "never"])
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("dry-run")
+                .help("Print the changes which would be made without saving them")
+                .long("dry-run")
+                .global(true),
+        )
         // tele
         .arg(
             Arg::with_name("WAYPOINT")
-                .help("Waypoint to tele to...")
-                .index(1)
-                .required_unless("add")
-                .required_unless("rm")
-                .required_unless("list"),
+                .help("Waypoint to tele to (optionally followed by /sub/dir), `-` for the previous directory, picked interactively if omitted")
+                .index(1),
         )
         // edit
         .arg(
@@ -39,26 +78,30 @@ pub fn parse_args() -> clap::ArgMatches<'static> {
                 .help("Change waypoint name")
                 .short("n")
                 .long("name")
-                .takes_value(true),
+                .takes_value(true)
+                .requires("WAYPOINT"),
         )
         .arg(
             Arg::with_name("path")
                 .help("Change waypoint path (default is working directory)")
                 .short("p")
-                .long("path"),
+                .long("path")
+                .requires("WAYPOINT"),
         )
         .arg(
             Arg::with_name("group")
                 .help("Change waypoint group")
                 .short("g")
                 .long("group")
-                .takes_value(true),
+                .takes_value(true)
+                .requires("WAYPOINT"),
         )
         .arg(
             Arg::with_name("ungroup")
                 .help("Remove waypoint from it's group")
                 .short("u")
-                .long("ungroup"),
+                .long("ungroup")
+                .requires("WAYPOINT"),
         )
         // add
         .subcommand(
@@ -67,7 +110,7 @@ pub fn parse_args() -> clap::ArgMatches<'static> {
                 .settings(global_settings!())
                 .arg(
                     Arg::with_name("name")
-                        .help("Name of waypoint (defaults to current folder name)")
+                        .help("Name of waypoint (defaults to the folder name)")
                         .index(1),
                 )
                 .arg(
@@ -83,6 +126,21 @@ pub fn parse_args() -> clap::ArgMatches<'static> {
                         .short("g")
                         .long("group")
                         .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("path")
+                        .help("Directory to add instead of the working directory")
+                        .short("p")
+                        .long("path")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("each")
+                        .help("Add every directory matching a glob, named after its folder")
+                        .long("each")
+                        .takes_value(true)
+                        .multiple(true)
+                        .conflicts_with_all(&["name", "group", "path"]),
                 ),
         )
         // rm
@@ -112,6 +170,12 @@ pub fn parse_args() -> clap::ArgMatches<'static> {
                         .long("dissolve")
                         .multiple(true)
                         .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("yes")
+                        .help("Do not ask before removing or ungrouping several waypoints")
+                        .short("y")
+                        .long("yes"),
                 ),
         )
         // list
@@ -139,6 +203,30 @@ pub fn parse_args() -> clap::ArgMatches<'static> {
                         .short("u")
                         .long("ungrouped"),
                 )
+                .arg(
+                    Arg::with_name("format")
+                        .help("Output format (defaults to table on a terminal, tsv otherwise)")
+                        .possible_values(&["table", "json", "tsv", "csv", "names", "paths"])
+                        .short("f")
+                        .long("format")
+                        .takes_value(true)
+                        .empty_values(false),
+                )
+                .arg(
+                    Arg::with_name("missing")
+                        .help("List only waypoints whose directory is missing or unreadable")
+                        .short("m")
+                        .long("missing"),
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .help("Sort this listing by name, path or frecency")
+                        .possible_values(&["name", "path", "frecency"])
+                        .short("s")
+                        .long("sort")
+                        .takes_value(true)
+                        .empty_values(false),
+                )
                 .arg(
                     Arg::with_name("default-view")
                         .help("Sets the default list view")
@@ -147,53 +235,275 @@ pub fn parse_args() -> clap::ArgMatches<'static> {
                         .takes_value(true)
                         .empty_values(false),
                 )
+                .arg(
+                    Arg::with_name("default-color")
+                        .help("Sets when to use colors")
+                        .possible_values(&["auto", "always", "never"])
+                        .long("default-color")
+                        .takes_value(true)
+                        .empty_values(false),
+                )
                 .arg(
                     Arg::with_name("default-sort")
                         .help("Sets the default sorting method")
-                        .possible_values(&["name", "path"])
+                        .possible_values(&["name", "path", "frecency"])
                         .long("default-sort")
                         .takes_value(true)
                         .empty_values(false),
                 ),
         )
-        .get_matches()
+        // init
+        .subcommand(
+            SubCommand::with_name("init")
+                .about("Prints shell integration, e.g. `eval \"$(tele init bash)\"`")
+                .settings(global_settings!())
+                .arg(
+                    Arg::with_name("shell")
+                        .help("Shell to generate integration for")
+                        .possible_values(&["bash", "zsh", "fish", "nushell", "sh"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("cmd")
+                        .help("Name of the generated function")
+                        .long("cmd")
+                        .takes_value(true)
+                        .default_value("t"),
+                ),
+        )
+        // doctor
+        .subcommand(
+            SubCommand::with_name("doctor")
+                .about("Checks waypoints for missing directories and duplicates")
+                .settings(global_settings!())
+                .arg(
+                    Arg::with_name("fix")
+                        .help("Relocate moved directories and remove missing ones without asking")
+                        .long("fix"),
+                )
+                .arg(
+                    Arg::with_name("search-root")
+                        .help("Where to search for moved directories (defaults to home directory)")
+                        .long("search-root")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("depth")
+                        .help("How deep to search for moved directories")
+                        .long("depth")
+                        .takes_value(true)
+                        .default_value("5"),
+                )
+                .arg(
+                    Arg::with_name("migrate-paths")
+                        .help("Rewrite paths inside the home directory to start with ~")
+                        .long("migrate-paths")
+                        .conflicts_with("restore"),
+                )
+                .arg(
+                    Arg::with_name("restore")
+                        .help("Restore waypoints from the backup taken before the last change")
+                        .long("restore"),
+                )
+                .arg(
+                    Arg::with_name("yes")
+                        .help("Apply changes without asking for confirmation")
+                        .short("y")
+                        .long("yes"),
+                ),
+        )
+        // history
+        .subcommand(
+            SubCommand::with_name("back")
+                .about("Go back in the teleport history")
+                .settings(global_settings!())
+                .arg(
+                    Arg::with_name("steps")
+                        .help("Number of directories to go back")
+                        .default_value("1"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("forward")
+                .about("Go forward in the teleport history")
+                .settings(global_settings!())
+                .arg(
+                    Arg::with_name("steps")
+                        .help("Number of directories to go forward")
+                        .default_value("1"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("List recently visited directories")
+                .settings(global_settings!()),
+        )
+        // scan
+        .subcommand(
+            SubCommand::with_name("scan")
+                .about("Find projects below a directory and add them as waypoints")
+                .settings(global_settings!())
+                .arg(
+                    Arg::with_name("root")
+                        .help("Directory to search (defaults to the working directory)")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("depth")
+                        .help("How deep to search for projects (0 only checks the directory itself)")
+                        .long("depth")
+                        .takes_value(true)
+                        .default_value("3"),
+                )
+                .arg(
+                    Arg::with_name("group")
+                        .help("Group to add the waypoints to")
+                        .short("g")
+                        .long("group")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("yes")
+                        .help("Add the waypoints without asking for confirmation")
+                        .short("y")
+                        .long("yes"),
+                ),
+        )
+        // import
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("Add waypoints from an export or from another directory jumper")
+                .settings(global_settings!())
+                .arg(
+                    Arg::with_name("file")
+                        .help("File to import, e.g. from `tele export`, ~/.z or zoxide's db.zo")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .help("Tool which wrote the file, if not tele")
+                        .long("from")
+                        .takes_value(true)
+                        .possible_values(&["zoxide", "autojump", "z", "fasd", "bashmarks"]),
+                )
+                .arg(
+                    Arg::with_name("group")
+                        .help("Group to add the waypoints to")
+                        .short("g")
+                        .long("group")
+                        .takes_value(true)
+                        .requires("from"),
+                )
+                .arg(
+                    Arg::with_name("merge")
+                        .help("Add to the current waypoints (default)")
+                        .long("merge")
+                        .conflicts_with_all(&["replace", "from"]),
+                )
+                .arg(
+                    Arg::with_name("replace")
+                        .help("Replace all current waypoints")
+                        .long("replace")
+                        .conflicts_with("from"),
+                )
+                .arg(
+                    Arg::with_name("on-conflict")
+                        .help("What to do with waypoints whose name is taken")
+                        .long("on-conflict")
+                        .takes_value(true)
+                        .possible_values(&["skip", "rename", "overwrite"])
+                        .default_value("skip"),
+                ),
+        )
+        // export
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Print waypoints for sharing")
+                .settings(global_settings!())
+                .arg(
+                    Arg::with_name("group")
+                        .help("Only export this group")
+                        .short("g")
+                        .long("group")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("Output format, sh being a standalone alias script")
+                        .short("f")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["json", "toml", "sh"])
+                        .default_value("json"),
+                ),
+        )
+        // journal
+        .subcommand(
+            SubCommand::with_name("undo")
+                .about("Revert the last change to the waypoints")
+                .settings(global_settings!()),
+        )
+        .subcommand(
+            SubCommand::with_name("redo")
+                .about("Apply the last undone change again")
+                .settings(global_settings!()),
+        )
+        .subcommand(
+            SubCommand::with_name("log")
+                .about("List recent changes to the waypoints")
+                .settings(global_settings!()),
+        )
+        // complete
+        .subcommand(
+            SubCommand::with_name("complete")
+                .about("Prints completion candidates for the last word")
+                .setting(AppSettings::Hidden)
+                .arg(
+                    Arg::with_name("words")
+                        .help("Words typed so far, the last one being completed")
+                        .multiple(true)
+                        .allow_hyphen_values(true),
+                ),
+        )
 }
 
 pub enum EditMatches {
     Name(String),
-    Path(Option<String>),
+    Path(Option<PathBuf>),
     Group(String),
     Ungroup,
 }
 
 
-pub fn parse_matches(matches: clap::ArgMatches<'static>) {
+pub fn parse_matches(matches: clap::ArgMatches<'static>) -> Result<()> {
+    // global flags given after a subcommand only show up in its matches
+    let sub_dry_run = matches!(matches.subcommand().1, Some(m) if m.is_present("dry-run"));
+    cmd::set_dry_run(matches.is_present("dry-run") || sub_dry_run);
     match matches.subcommand() {
         ("add", Some(matches)) => {
-            let name = cmd::parse_name(matches.value_of("name"));
-            if matches.is_present("group") {
-                let group = matches.value_of("group");
-                cmd::add(&name, group)
-            } else if matches.is_present("group-flag") {
-                let group = matches.value_of("group-flag");
-                cmd::add(&name, group)
-            } else {
-                cmd::add(&name, None)
+            let group = matches.value_of("group").or_else(|| matches.value_of("group-flag"));
+            if let Some(patterns) = matches.values_of_os("each") {
+                let patterns: Vec<_> = patterns.map(Path::new).collect();
+                return cmd::add_each(&patterns, group);
             }
+            let path = match matches.value_of_os("path") {
+                Some(p) => Filesystem::resolve_dir(Path::new(p))?,
+                None => Filesystem::current_dir()?,
+            };
+            let name = cmd::parse_name(matches.value_of("name"), &path)?;
+            cmd::add(&name, &path, group)
         }
         ("rm", Some(matches)) => {
-            if matches.is_present("name") {
-                let names: Vec<_> = matches.values_of("name").unwrap().collect();
-                cmd::rm(Some(names), None)
+            let yes = matches.is_present("yes");
+            let names: Option<Vec<_>> = matches.values_of("name").map(Iterator::collect);
+            let groups: Option<Vec<_>> = matches.values_of("group").map(Iterator::collect);
+            if names.is_some() || groups.is_some() {
+                cmd::rm(names, groups, yes)?
             }
-            if matches.is_present("group") {
-                let groups: Vec<_> = matches.values_of("group").unwrap().collect();
-                cmd::rm(None, Some(groups))
-            }
-            if matches.is_present("dissolve") {
-                let groups: Vec<_> = matches.values_of("dissolve").unwrap().collect();
-                cmd::dissolve(groups)
+            if let Some(groups) = matches.values_of("dissolve") {
+                cmd::dissolve(groups.collect(), yes)?
             }
+            Ok(())
         }
         ("list", Some(matches)) => {
             // set "default-view"
@@ -201,49 +511,137 @@ pub fn parse_matches(matches: clap::ArgMatches<'static>) {
                 let view = matches.value_of("default-view").unwrap();
                 TELE.config()
                     .set("default-view", Some(view))
-                    .unwrap_or(());
-                println!("default list view set to '{}'", &view)
+                    .map_err(|e| Error::Config(e.to_string()))?;
+                println!("default list view set to '{}'", &view);
+                Ok(())
+            // set "default-color"
+            } else if matches.is_present("default-color") {
+                let color = matches.value_of("default-color").unwrap();
+                TELE.config()
+                    .set("color", Some(color))
+                    .map_err(|e| Error::Config(e.to_string()))?;
+                println!("colors are now set to '{}'", &color);
+                Ok(())
             // set "default-sort"
             } else if matches.is_present("default-sort") {
                 let sort = matches.value_of("default-sort").unwrap();
                 TELE.config()
                     .set("default-sort", Some(sort))
-                    .unwrap_or(());
-                cmd::reload_list();
-                println!("list is now sorted by '{}'", &sort)
+                    .map_err(|e| Error::Config(e.to_string()))?;
+                cmd::reload_list()?;
+                println!("list is now sorted by '{}'", &sort);
+                Ok(())
             } else {
-                if matches.is_present("all") {
-                    cmd::list(ListView::All)
+                let sort = matches.value_of("sort").map(ListSort::parse);
+                let missing = matches.is_present("missing");
+                let format = match matches.value_of("format") {
+                    Some(f) => Format::parse(f),
+                    None if term::stdout_is_tty() => Format::Table,
+                    None => Format::Tsv,
+                };
+                let view = if matches.is_present("all") {
+                    ListView::All
                 } else if matches.is_present("group") {
-                    let group = matches.value_of("group").unwrap();
-                    cmd::list(ListView::Group(group.to_string()))
+                    ListView::Group(matches.value_of("group").unwrap().to_string())
                 } else if matches.is_present("ungrouped") {
-                    cmd::list(ListView::Groupless)
+                    ListView::Groupless
+                } else if missing {
+                    ListView::All
                 } else {
-                    match ListView::from_config() {
-                        ListView::All => cmd::list(ListView::All),
-                        _ => cmd::list(ListView::Groupless),
-                    }
+                    config::default_view()
+                };
+                cmd::list(view, sort, missing, format)
+            }
+        }
+        ("init", Some(matches)) => {
+            let shell = Shell::parse(matches.value_of("shell").unwrap()).unwrap();
+            let name = matches.value_of("cmd").unwrap();
+            cmd::init(shell, name)
+        }
+        ("doctor", Some(matches)) => {
+            let yes = matches.is_present("yes");
+            if matches.is_present("restore") {
+                cmd::restore(yes)
+            } else if matches.is_present("migrate-paths") {
+                cmd::migrate_paths()
+            } else {
+                let search = Search {
+                    root: matches
+                        .value_of_os("search-root")
+                        .map(PathBuf::from)
+                        .or_else(|| env::var_os("HOME").map(PathBuf::from))
+                        .unwrap_or_else(|| PathBuf::from("/")),
+                    depth: value_t!(matches, "depth", usize).unwrap_or_else(|e| e.exit()),
+                };
+                cmd::doctor(yes, matches.is_present("fix"), search)
+            }
+        }
+        ("back", Some(matches)) => cmd::back(value_t!(matches, "steps", usize).unwrap_or_else(|e| e.exit())),
+        ("forward", Some(matches)) => cmd::forward(value_t!(matches, "steps", usize).unwrap_or_else(|e| e.exit())),
+        ("history", Some(_)) => cmd::history(),
+        ("scan", Some(matches)) => {
+            let root = match matches.value_of_os("root") {
+                Some(r) => Filesystem::resolve_dir(Path::new(r))?,
+                None => Filesystem::current_dir()?,
+            };
+            let depth = value_t!(matches, "depth", usize).unwrap_or_else(|e| e.exit());
+            cmd::scan(&root, depth, matches.value_of("group"), matches.is_present("yes"))
+        }
+        ("import", Some(matches)) => {
+            let file = Path::new(matches.value_of_os("file").unwrap());
+            match matches.value_of("from").and_then(Source::parse) {
+                Some(source) => cmd::import_from(source, file, matches.value_of("group")),
+                None => {
+                    let conflict = Conflict::parse(matches.value_of("on-conflict").unwrap());
+                    cmd::import_file(file, matches.is_present("replace"), conflict)
                 }
             }
         }
+        ("export", Some(matches)) => {
+            let format = share::Format::parse(matches.value_of("format").unwrap());
+            cmd::export(matches.value_of("group"), format)
+        }
+        ("undo", Some(_)) => cmd::undo(),
+        ("redo", Some(_)) => cmd::redo(),
+        ("log", Some(_)) => cmd::log(),
+        ("complete", Some(matches)) => {
+            let words: Vec<_> = matches.values_of("words").map_or(Vec::new(), Iterator::collect);
+            cmd::complete(&words)
+        }
+        ("", None) if !matches.is_present("WAYPOINT") => {
+            if picker::available() {
+                cmd::pick()
+            } else {
+                let mut out = io::stdout();
+                app().write_help(&mut out).ok();
+                writeln!(out).ok();
+                Ok(())
+            }
+        }
         ("", None) => {
             let wp = matches.value_of("WAYPOINT").unwrap();
             if matches.is_present("name") {
                 let name = matches.value_of("name").unwrap();
                 cmd::edit(wp, EditMatches::Name(name.to_string()))
             } else if matches.is_present("path") {
-                let path = matches.value_of("path").map(str::to_string);
+                let path = matches.value_of_os("path").map(PathBuf::from);
                 cmd::edit(wp, EditMatches::Path(path))
             } else if matches.is_present("group") {
                 let group = matches.value_of("group").unwrap();
                 cmd::edit(wp, EditMatches::Group(group.to_string()))
             } else if matches.is_present("ungroup") {
                 cmd::edit(wp, EditMatches::Ungroup)
+            } else if wp == "-" {
+                cmd::previous()
             } else {
                 cmd::tele(wp)
             }
         }
-        _ => unreachable!(),
+        // extra arguments after the waypoint end up as an external subcommand
+        (arg, _) => clap::Error::with_description(
+            &format!("Found argument '{}' which wasn't expected", arg),
+            clap::ErrorKind::UnknownArgument,
+        )
+        .exit(),
     }
 }
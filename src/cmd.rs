@@ -1,123 +1,842 @@
-use crate::cli::{EditMatches};
+use crate::cli::EditMatches;
+use crate::complete;
+use crate::config;
+use crate::doctor::{self, Mode, Search};
+use crate::format::{self, Format};
+use crate::picker;
+use crate::prompt;
+use crate::shell::{self, Shell};
 use crate::table;
-use crate::waypoints::{List, ListView, Waypoint};
 use crate::filesystem::Filesystem;
+use std::cmp::Ordering;
+use std::{env, fs};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicBool};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tele::resolve::{self, Resolution};
+use tele::import::{self, Source};
+use tele::journal::{self, Journal};
+use tele::share::{self, Conflict};
+use tele::{paths, storage, walk};
+use tele::{validate_name, Error, FileStore, History, List, ListSort, ListView, Result, Store, StoreLock, Waypoint};
 
-pub fn add(name: &str, group: Option<&str>) {
-    let mut list = List::load();
-    match list.get_entry(&name) {
-        Some(w) => println!("'{}' is already assigned to: {}", &name, &w.path),
-        None => {
-            let w = Waypoint::new(&name, group);
-            list.0.push(w);
-            list.save();
-            println!("'{}' added to waypoints", &name)
+/// Reports a change made to the waypoints
+///
+/// Left out during a dry run, which lists the planned changes instead.
+macro_rules! done {
+    ($($arg:tt)*) => {
+        if !dry_run() {
+            println!($($arg)*)
         }
+    };
+}
+
+pub fn add(name: &str, path: &Path, group: Option<&str>) -> Result<()> {
+    modify(|list| {
+        list.add_entry(Waypoint::new(name, &paths::contract(path), group))?;
+        done!("'{}' added to waypoints", &name);
+        Ok(())
+    })
+}
+
+/// Adds every directory matching `patterns`, named after its folder
+///
+/// Directories which cannot be added are reported and skipped.
+pub fn add_each(patterns: &[&Path], group: Option<&str>) -> Result<()> {
+    let cwd = Filesystem::current_dir()?;
+    modify(|list| {
+        for pattern in patterns {
+            let dirs = walk::glob_dirs(&cwd.join(paths::expand(pattern)));
+            if dirs.is_empty() {
+                println!("no directories match '{}'", pattern.display());
+            }
+            for dir in dirs {
+                let dir = dir.canonicalize().unwrap_or(dir);
+                let path = paths::contract(&dir);
+                if let Some(w) = list.0.iter().find(|w| w.expanded_path() == dir) {
+                    println!("'{}' skipped, already added as '{}'", path.display(), w.name);
+                    continue;
+                }
+                let result = Filesystem::dir_name(&dir)
+                    .and_then(|name| list.add_entry(Waypoint::new(&name, &path, group)).map(|_| name));
+                match result {
+                    Ok(name) => done!("'{}' added to waypoints", name),
+                    Err(e) => println!("'{}' skipped: {}", path.display(), e),
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Removes waypoints by name and by group
+///
+/// Removing groups asks first if they hold several waypoints, unless `yes`.
+pub fn rm(names: Option<Vec<&str>>, groups: Option<Vec<&str>>, yes: bool) -> Result<()> {
+    modify(|list| {
+        let mut removed = match &names {
+            Some(req) => list.remove_entries(req)?,
+            None => Vec::new(),
+        };
+        if let Some(req) = &groups {
+            removed.extend(list.remove_group(req)?);
+            confirm_bulk(yes, &removed, "remove")?;
+        }
+        for w in removed {
+            done!("'{}' removed from waypoints", &w.name)
+        }
+        for g in groups.unwrap_or_default() {
+            done!("group '{}' removed", &g)
+        }
+        Ok(())
+    })
+}
+
+pub fn dissolve(groups: Vec<&str>, yes: bool) -> Result<()> {
+    modify(|list| {
+        let dissolved = list.dissolve_groups(&groups)?;
+        confirm_bulk(yes, &dissolved, "ungroup")?;
+        for w in dissolved {
+            match list.get_entry(&w.name).and_then(|w| w.group.as_deref()) {
+                Some(g) => done!("'{}' moved to group '{}'", &w.name, g),
+                None => done!("'{}' ungrouped", &w.name),
+            }
+        }
+        for g in &groups {
+            done!("group '{}' has been dissolved", g)
+        }
+        Ok(())
+    })
+}
+
+/// Lists `affected` and asks whether to `action` them if there are several
+///
+/// Both go to stderr, as the shell integration captures stdout.
+/// Never asks with `yes` or during a dry run, which changes nothing anyway.
+/// Fails when the answer is no or there is no terminal to ask on, so
+/// [`modify`] saves nothing.
+fn confirm_bulk(yes: bool, affected: &[Waypoint], action: &str) -> Result<()> {
+    if yes || dry_run() || affected.len() < 2 {
+        return Ok(());
+    }
+    let question = format!("{} {} waypoints", action, affected.len());
+    if !prompt::interactive() {
+        return Err(Error::NeedsConfirmation(question));
+    }
+    for w in affected {
+        eprintln!(" {} -> {}", w.name, w.path.display())
+    }
+    if prompt::confirm(&format!("{}?", question)) {
+        Ok(())
+    } else {
+        Err(Error::Cancelled)
     }
 }
 
-pub fn rm(names: Option<Vec<&str>>, groups: Option<Vec<&str>>) {
-    if let Some(req) = names {
-        let list = List::load();
-        if let Ok(l) = list.remove_entries(req) {
-            l.save()
+pub fn edit(wp: &str, kind: EditMatches) -> Result<()> {
+    modify(|list| {
+        match kind {
+            EditMatches::Name(name) => {
+                list.rename_entry(wp, &name)?;
+                done!("'{}' renamed to '{}'", wp, name)
+            }
+            EditMatches::Path(path) => {
+                let path = match path {
+                    Some(p) => paths::contract(&p),
+                    None => paths::contract(&Filesystem::current_dir()?),
+                };
+                let old = list.repath_entry(wp, &path)?;
+                done!(
+                    "path changed for waypoint '{}':\n old: {}\n new: {}",
+                    wp,
+                    old.path.display(),
+                    path.display()
+                )
+            }
+            EditMatches::Group(group) => {
+                let old = list.regroup_entry(wp, &group)?;
+                // as stored, e.g. `a/b` for `a//b/`
+                let group = list.get_entry(wp).and_then(|w| w.group.clone()).unwrap_or_default();
+                match old.group {
+                    Some(old_group) => done!(
+                        "'{}' has been regrouped from '{}' to '{}'",
+                        wp, old_group, group
+                    ),
+                    None => done!("'{}' has been added to group '{}'", wp, group),
+                }
+            }
+            EditMatches::Ungroup => {
+                let old = list.ungroup_entry(wp)?;
+                done!(
+                    "'{}' has been removed from group '{}'",
+                    wp,
+                    old.group.unwrap_or_default()
+                )
+            }
         }
+        Ok(())
+    })
+}
+
+pub fn reload_list() -> Result<()> {
+    let (store, _lock) = open_locked()?;
+    let list = store.load()?;
+    save(&store, list)
+}
+
+pub fn list(view: ListView, sort: Option<ListSort>, missing: bool, format: Format) -> Result<()> {
+    let list = FileStore::open_default().load()?;
+    let (view, filtered) = match list.filter_view(&view) {
+        None if matches!(view, ListView::Groupless) => (ListView::All, list.filter_view(&ListView::All)),
+        filtered => (view, filtered),
+    };
+    if let (ListView::Group(g), None) = (&view, &filtered) {
+        return Err(Error::GroupNotFound(g.clone()));
+    }
+    let mut shown = sorted(filtered.unwrap_or_default(), sort);
+    if missing {
+        shown.0.retain(|w| w.status().is_problem())
+    }
+    match (format, view) {
+        (Format::Table, _) if shown.0.is_empty() && missing => println!("all directories exist"),
+        (Format::Table, _) if shown.0.is_empty() => println!("no waypoints defined"),
+        (Format::Table, ListView::Group(g)) if !missing => table::print_group(shown, &g),
+        (Format::Table, ListView::Groupless) if !missing => table::print_groupless(shown),
+        (Format::Table, _) => table::print_all(shown),
+        (f, _) => match format::print(&shown, &f) {
+            // the reader went away, e.g. `tele list | head`
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => (),
+            Err(e) => return Err(Error::io("stdout", e)),
+            Ok(()) => (),
+        },
+    }
+    Ok(())
+}
+
+fn sorted(list: List, sort: Option<ListSort>) -> List {
+    match sort {
+        Some(s) => list.sort_by(s),
+        None => list,
     }
-    if let Some(req) = groups {
-        let list = List::load();
-        if let Ok(l) = list.remove_group(req) {
-            l.save()
+}
+
+/// Opens the default store, locked for a load-modify-save cycle
+fn open_locked() -> Result<(FileStore, StoreLock)> {
+    let store = FileStore::open_default();
+    let lock = store.lock()?;
+    Ok((store, lock))
+}
+
+/// Sorts `list` by the configured default and writes it to `store`
+fn save(store: &FileStore, list: List) -> Result<()> {
+    store.save(&list.sort_by(config::default_sort()))
+}
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Makes [`modify`] print the changes instead of saving them, see `--dry-run`
+pub fn set_dry_run(dry_run: bool) {
+    DRY_RUN.store(dry_run, atomic::Ordering::Relaxed)
+}
+
+fn dry_run() -> bool {
+    DRY_RUN.load(atomic::Ordering::Relaxed)
+}
+
+/// Runs `change` on the waypoints in a locked load-modify-save cycle
+///
+/// The list is only saved if `change` altered it, in which case the change
+/// is recorded in the journal for `tele undo`. During a dry run the change
+/// is only printed.
+fn modify<T>(change: impl FnOnce(&mut List) -> Result<T>) -> Result<T> {
+    let (store, _lock) = open_locked()?;
+    let before = store.load()?;
+    let mut list = before.clone();
+    let out = change(&mut list)?;
+    if dry_run() {
+        print_dry_run(&before, &list);
+    } else if list != before {
+        save(&store, list.clone())?;
+        Journal::open_default().record(&command_line(), &before, &list)?;
+    }
+    Ok(out)
+}
+
+/// Summarizes the difference between `before` and `after`
+fn print_dry_run(before: &List, after: &List) {
+    let (removed, added) = journal::diff(before, after);
+    if !removed.is_empty() || !added.is_empty() {
+        println!("planned changes:");
+    }
+    for w in &removed {
+        println!(" - {}", describe(w))
+    }
+    for w in &added {
+        println!(" + {}", describe(w))
+    }
+    println!("dry run, no changes made");
+}
+
+/// `name -> path (group)`
+fn describe(w: &Waypoint) -> String {
+    match &w.group {
+        Some(g) => format!("{} -> {} ({})", w.name, w.path.display(), g),
+        None => format!("{} -> {}", w.name, w.path.display()),
+    }
+}
+
+/// The command being run, as shown by `tele log`
+fn command_line() -> String {
+    env::args_os()
+        .skip(1)
+        .fold(String::from("tele"), |line, arg| line + " " + &arg.to_string_lossy())
+}
+
+pub fn tele(query: &str) -> Result<()> {
+    let (name, sub_path) = split_sub_path(query);
+    let (store, _lock) = open_locked()?;
+    let mut list = store.load()?;
+    let (name, path) = match resolve::resolve(&list, name) {
+        Resolution::Found(w) => {
+            let path = match sub_path {
+                Some(sub) => w.expanded_path().join(sub),
+                None => w.expanded_path(),
+            };
+            (w.name.clone(), path)
         }
+        Resolution::Ambiguous(candidates) => {
+            table::print_flat(List(candidates.into_iter().cloned().collect()));
+            return Err(Error::Ambiguous(name.to_string()));
+        }
+        Resolution::NotFound => return Err(Error::NotFound(name.to_string())),
+    };
+    if sub_path.is_some() && !path.is_dir() {
+        return Err(Error::NotADirectory(path));
+    }
+    if dry_run() {
+        return teleport(&path);
+    }
+    list.visit_entry(&name)?;
+    save(&store, list)?;
+    if let Err(e) = record_history(&path) {
+        eprintln!("warning: could not record history: {}", e)
     }
+    teleport(&path)
+}
+
+/// Prints `path` for the shell integration to `cd` into
+fn teleport(path: &Path) -> Result<()> {
+    let mut out = io::stdout();
+    format::write_path(&mut out, path)
+        .and_then(|_| writeln!(out))
+        .and_then(|_| out.flush())
+        .map_err(|e| Error::io("stdout", e))?;
+    std::process::exit(2)
 }
 
-pub fn dissolve(groups: Vec<&str>) {
-    let list = List::load();
-    if let Ok(l) = list.dissolve_groups(groups) {
-        l.save()
+/// Records a teleport from the working directory to `to`
+fn record_history(to: &Path) -> Result<()> {
+    let path = History::default_path();
+    let mut history = History::load(&path)?;
+    history.record(&Filesystem::current_dir()?, to);
+    history.save(&path)
+}
+
+/// Teleports to the last directory before the working directory, like `cd -`
+pub fn previous() -> Result<()> {
+    let _lock = open_locked()?;
+    let path = History::default_path();
+    let mut history = History::load(&path)?;
+    let current = Filesystem::current_dir()?;
+    let dir = history.previous(&current).ok_or(Error::EndOfHistory("back"))?.to_path_buf();
+    if !dir.is_dir() {
+        return Err(Error::NotADirectory(dir));
+    }
+    history.record(&current, &dir);
+    if !dry_run() {
+        history.save(&path)?;
     }
+    teleport(&dir)
 }
 
-pub fn edit(wp: &str, kind: EditMatches) {
-    match kind {
-        EditMatches::Name(name) => {
-            let list = List::load();
-            if let Ok(l) = list.rename_entry(wp, &name) {
-                l.save()
-            }
+pub fn back(steps: usize) -> Result<()> {
+    travel(|h| h.back(steps).map(Path::to_path_buf), "back")
+}
+
+pub fn forward(steps: usize) -> Result<()> {
+    travel(|h| h.forward(steps).map(Path::to_path_buf), "forward")
+}
+
+/// Moves the history cursor with `step` and teleports to the new position
+fn travel(step: impl FnOnce(&mut History) -> Option<PathBuf>, direction: &'static str) -> Result<()> {
+    let _lock = open_locked()?;
+    let path = History::default_path();
+    let mut history = History::load(&path)?;
+    let dir = step(&mut history).ok_or(Error::EndOfHistory(direction))?;
+    if !dir.is_dir() {
+        return Err(Error::NotADirectory(dir));
+    }
+    if !dry_run() {
+        history.save(&path)?;
+    }
+    teleport(&dir)
+}
+
+/// Lists the history, numbering each directory by the steps `back` needs
+pub fn history() -> Result<()> {
+    let history = History::load(&History::default_path())?;
+    if history.entries.is_empty() {
+        println!("no history yet");
+    }
+    for (i, dir) in history.entries.iter().enumerate().rev() {
+        let steps = history.cursor as i64 - i as i64;
+        let marker = if steps == 0 { '>' } else { ' ' };
+        println!("{} {:>3}  {}", marker, steps, dir.display())
+    }
+    Ok(())
+}
+
+pub fn undo() -> Result<()> {
+    step_journal(true)
+}
+
+pub fn redo() -> Result<()> {
+    step_journal(false)
+}
+
+/// Reverts the last change from the journal, or reapplies the last undone one
+fn step_journal(undo: bool) -> Result<()> {
+    let journal = Journal::open_default();
+    let (store, _lock) = open_locked()?;
+    let mut list = store.load()?;
+    let before = list.clone();
+    let step = if undo { journal.next_undo()? } else { journal.next_redo()? };
+    let (done, nothing) = match (undo, dry_run()) {
+        (true, false) => ("undid", "nothing to undo"),
+        (true, true) => ("would undo", "nothing to undo"),
+        (false, false) => ("redid", "nothing to redo"),
+        (false, true) => ("would redo", "nothing to redo"),
+    };
+    let entry = match step {
+        Some(entry) => entry,
+        None => {
+            println!("{}", nothing);
+            return Ok(());
         }
-        EditMatches::Path(path) => {
-            let list = List::load();
-            if let Some(p) = path {
-                if let Ok(l) = list.repath_entry(wp, &p) {
-                    l.save()
-                }
+    };
+    if undo {
+        entry.revert(&mut list)
+    } else {
+        entry.reapply(&mut list)
+    }
+    if let journal::Kind::Change { command, .. } = &entry.kind {
+        println!("{} `{}`", done, command)
+    }
+    if dry_run() {
+        print_dry_run(&before, &list);
+        return Ok(());
+    }
+    save(&store, list)?;
+    if undo {
+        journal.record_undo(&entry)
+    } else {
+        journal.record_redo(&entry)
+    }
+}
+
+/// Lists the journaled changes, most recent first
+pub fn log() -> Result<()> {
+    let changes = Journal::open_default().changes()?;
+    if changes.is_empty() {
+        println!("no changes yet");
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    for (entry, undone) in changes.iter().rev() {
+        let (command, marker) = match &entry.kind {
+            journal::Kind::Change { command, .. } if *undone => (command, "  (undone)"),
+            journal::Kind::Change { command, .. } => (command, ""),
+            journal::Kind::Reset { command } => (command, "  (earlier changes can no longer be undone)"),
+            _ => continue,
+        };
+        println!(
+            "{:>4}  {:<16} {}{}",
+            entry.id,
+            age(now.saturating_sub(entry.time)),
+            command,
+            marker
+        )
+    }
+    Ok(())
+}
+
+/// `secs` as a rough age such as "5 minutes ago"
+fn age(secs: u64) -> String {
+    let (n, unit) = match secs {
+        s if s < 60 => return "just now".to_string(),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 86400 => (s / 3600, "hour"),
+        s => (s / 86400, "day"),
+    };
+    format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" })
+}
+
+/// Teleports to a waypoint chosen in the interactive picker
+pub fn pick() -> Result<()> {
+    let list = FileStore::open_default().load()?;
+    if list.0.is_empty() {
+        println!("no waypoints defined");
+        return Ok(());
+    }
+    match picker::pick(&list).map_err(|e| Error::io("/dev/tty", e))? {
+        Some(name) => tele(&name),
+        None => Ok(()),
+    }
+}
+
+/// Splits `waypoint/sub/dir` into the waypoint name and relative sub-path
+fn split_sub_path(query: &str) -> (&str, Option<&str>) {
+    match query.find('/') {
+        Some(i) => {
+            let sub = query[i + 1..].trim_matches('/');
+            (&query[..i], if sub.is_empty() { None } else { Some(sub) })
+        }
+        None => (query, None),
+    }
+}
+
+pub fn doctor(yes: bool, fix: bool, search: Search) -> Result<()> {
+    let mode = if fix {
+        Mode::Fix
+    } else if prompt::interactive() {
+        Mode::Interactive
+    } else {
+        Mode::Report
+    };
+    // fixes are saved even if some problems remain
+    let checked = modify(|list| Ok(doctor::check(list, &mode, &search)));
+    match checked {
+        Ok(0) => Ok(()),
+        Ok(unresolved) => Err(Error::Unresolved(unresolved)),
+        Err(e @ Error::Parse { .. }) => {
+            let (store, _lock) = open_locked()?;
+            println!("{}", e);
+            if let Error::Parse { path, source } = &e {
+                report_parse_error(path, source);
+            }
+            let list = storage::salvage(&store.read_to_string()?);
+            eprintln!("recovered {} waypoints from the damaged file:", list.0.len());
+            for w in &list.0 {
+                eprintln!(" {} -> {}", w.name, w.path.display())
+            }
+            if dry_run() {
+                println!("dry run, no changes made")
+            } else if yes || fix || prompt::confirm("replace the damaged file with the recovered waypoints?") {
+                let damaged = store.replace_damaged(&list)?;
+                Journal::open_default().record_reset(&command_line())?;
+                println!("damaged file kept at '{}'", damaged.display())
             } else {
-                if let Ok(l) = list.repath_entry(wp, &Filesystem::current_dir()) {
-                    l.save()
-                }
+                println!("no changes made")
             }
+            Ok(())
         }
-        EditMatches::Group(group) => {
-            let list = List::load();
-            if let Ok(l) = list.regroup_entry(wp, &group) {
-                l.save()
-            }
+        Err(e) => Err(e),
+    }
+}
+
+/// Rewrites paths inside the home directory to start with `~`
+pub fn migrate_paths() -> Result<()> {
+    modify(|list| {
+        let moved: Vec<(String, PathBuf)> = list
+            .0
+            .iter()
+            .filter_map(|w| paths::migrate(&w.path).map(|p| (w.name.clone(), p)))
+            .collect();
+        if moved.is_empty() {
+            println!("no paths to migrate");
+            return Ok(());
+        }
+        for (name, path) in &moved {
+            let old = list.repath_entry(name, path)?;
+            done!("'{}': {} -> {}", name, old.path.display(), path.display())
+        }
+        Ok(())
+    })
+}
+
+pub fn restore(yes: bool) -> Result<()> {
+    let (store, _lock) = open_locked()?;
+    let backup = store.load_backup()?;
+    if dry_run() {
+        println!("would restore {} waypoints from the backup", backup.0.len());
+        println!("dry run, no changes made");
+        return Ok(());
+    }
+    if yes
+        || prompt::confirm(&format!(
+            "replace '{}' with its backup ({} waypoints)?",
+            store.path().display(),
+            backup.0.len()
+        ))
+    {
+        let current = store.load().ok();
+        let damaged = store.replace_damaged(&backup)?;
+        let journal = Journal::open_default();
+        match current {
+            Some(current) => journal.record(&command_line(), &current, &backup)?,
+            None => journal.record_reset(&command_line())?,
+        }
+        println!(
+            "restored {} waypoints, previous file kept at '{}'",
+            backup.0.len(),
+            damaged.display()
+        );
+    }
+    Ok(())
+}
+
+/// Helps the user out of errors which would otherwise break every command
+pub fn recover(error: &Error) {
+    if let Error::Parse { path, source } = error {
+        let store = FileStore::open_default();
+        if path != store.path() {
+            return;
         }
-        EditMatches::Ungroup => {
-            let list = List::load();
-            if let Ok(l) = list.ungroup_entry(wp) {
-                l.save()
+        report_parse_error(path, source);
+        match store.load_backup() {
+            Ok(backup) => {
+                let question = format!("restore the backup ({} waypoints)?", backup.0.len());
+                if prompt::confirm(&question) {
+                    match restore(true) {
+                        Ok(()) => (),
+                        Err(e) => eprintln!("error: {}", e),
+                    }
+                } else {
+                    eprintln!(
+                        "run `tele doctor --restore` to restore the backup \
+                         or `tele doctor` to salvage the remaining waypoints"
+                    )
+                }
             }
+            Err(_) => eprintln!("run `tele doctor` to salvage the remaining waypoints"),
         }
     }
 }
 
-pub fn reload_list() {
-    let list = List::load();
-    list.save()
+/// Prints the offending line of a JSON file with a marker under the error
+fn report_parse_error(path: &Path, source: &serde_json::Error) {
+    let text = std::fs::read_to_string(path).unwrap_or_default();
+    if let Some(line) = text.lines().nth(source.line().saturating_sub(1)) {
+        eprintln!("{:>5} | {}", source.line(), line);
+        eprintln!("{:>5} | {:>2$}", "", "^", source.column().max(1));
+    }
 }
 
-pub fn list(view: ListView) {
-    match view {
-        ListView::All => {
-            let list = List::load();
-            if list.0.is_empty() {
-                println!("no waypoints defined")
-            } else {
-                table::print_all(list)
-            }
+pub fn init(shell: Shell, name: &str) -> Result<()> {
+    if shell::valid_function_name(name) {
+        print!("{}", shell::init_script(shell, name));
+        Ok(())
+    } else {
+        Err(Error::InvalidFunctionName(name.to_string()))
+    }
+}
+
+/// Prints completion candidates, staying silent if the list cannot be loaded
+pub fn complete(words: &[&str]) -> Result<()> {
+    if let Ok(list) = FileStore::open_default().load() {
+        for c in complete::candidates(&list, words) {
+            println!("{}", c)
         }
-        ListView::Group(g) => {
-            let list = List::load_group(&g);
-            if let Some(l) = list {
-                table::print_group(l, &g)
-            } else {
-                println!("'{}' is an empty group", &g)
+    }
+    Ok(())
+}
+
+/// Proposes a waypoint for every project found below `root`
+pub fn scan(root: &Path, depth: usize, group: Option<&str>, yes: bool) -> Result<()> {
+    modify(|list| {
+        let mut proposed: Vec<Waypoint> = Vec::new();
+        for dir in walk::find_projects(root, depth) {
+            if list.0.iter().any(|w| w.expanded_path() == dir) {
+                continue;
+            }
+            let name = free_name(&Filesystem::dir_name(&dir)?, &dir, list, &proposed);
+            match validate_name(&name) {
+                Ok(()) => proposed.push(Waypoint::new(&name, &paths::contract(&dir), group)),
+                Err(e) => eprintln!("'{}' skipped: {}", dir.display(), e),
             }
         }
-        ListView::Groupless => {
-            let list = List::load_groupless();
-            if let Some(l) = list {
-                table::print_groupless(l)
-            } else {
-                self::list(ListView::All)
+        if proposed.is_empty() {
+            println!("no new projects found below '{}'", root.display());
+            return Ok(());
+        }
+        // on stderr like the question, as the shell integration captures stdout
+        for w in &proposed {
+            eprintln!(" {} -> {}", w.name, w.path.display())
+        }
+        if !yes && !dry_run() && !prompt::confirm(&format!("add {} waypoints?", proposed.len())) {
+            println!("no changes made");
+            return Ok(());
+        }
+        for w in proposed {
+            done!("'{}' added to waypoints", w.name);
+            list.add_entry(w)?;
+        }
+        Ok(())
+    })
+}
+
+/// Adds the directories from another jumper's database
+pub fn import_from(source: Source, file: &Path, group: Option<&str>) -> Result<()> {
+    let data = fs::read(file).map_err(|e| Error::io(file, e))?;
+    let mut entries = import::parse(source, &data).ok_or_else(|| Error::BadImport {
+        path: file.to_path_buf(),
+        format: source.name(),
+    })?;
+    // the most used directories get the plain names
+    entries.sort_by(|a, b| b.rank.partial_cmp(&a.rank).unwrap_or(Ordering::Equal));
+
+    modify(|list| {
+        let mut proposed: Vec<Waypoint> = Vec::new();
+        let mut missing = 0;
+        for e in entries {
+            let dir = e.path;
+            if !dir.is_dir() {
+                missing += 1;
+                continue;
+            }
+            if list.0.iter().chain(&proposed).any(|w| w.expanded_path() == dir) {
+                continue;
             }
+            let base = match e.name.map_or_else(|| Filesystem::dir_name(&dir), Ok) {
+                Ok(base) => base,
+                Err(err) => {
+                    println!("'{}' skipped: {}", dir.display(), err);
+                    continue;
+                }
+            };
+            let name = free_name(&base, &dir, list, &proposed);
+            match validate_name(&name) {
+                Ok(()) => proposed.push(Waypoint {
+                    visits: e.rank.round().max(1.0) as u64,
+                    last_visit: e.last_visit,
+                    ..Waypoint::new(&name, &paths::contract(&dir), group)
+                }),
+                Err(err) => println!("'{}' skipped: {}", dir.display(), err),
+            }
+        }
+        if missing > 0 {
+            println!("{} entries skipped, their directories no longer exist", missing)
         }
+        if proposed.is_empty() {
+            println!("nothing new to import");
+            return Ok(());
+        }
+        for w in &proposed {
+            done!(" {} -> {}", w.name, w.path.display())
+        }
+        let count = proposed.len();
+        for w in proposed {
+            list.add_entry(w)?;
+        }
+        done!("imported {} waypoints", count);
+        Ok(())
+    })
+}
+
+/// Prints the waypoints, or those in `group`, for sharing
+pub fn export(group: Option<&str>, format: share::Format) -> Result<()> {
+    let list = FileStore::open_default().load()?;
+    let list = match group {
+        Some(g) => list.filter_group(Some(g)).ok_or_else(|| Error::GroupNotFound(g.to_string()))?,
+        None => list,
+    };
+    let text = share::export(&list.sort_by(ListSort::Name), format);
+    match io::stdout().write_all(text.as_bytes()) {
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|e| Error::io("stdout", e)),
     }
 }
 
-pub fn tele(name: &str) {
-    let list = List::load();
-    match list.get_entry(name) {
-        Some(w) => {
-            println!("{}", &w.path);
-            std::process::exit(2)
+/// Adds the waypoints from a file written by `export`
+///
+/// With `replace` the current waypoints are dropped first.
+pub fn import_file(file: &Path, replace: bool, conflict: Conflict) -> Result<()> {
+    let data = fs::read(file).map_err(|e| Error::io(file, e))?;
+    let imported = share::parse(file, &data)?;
+    modify(|list| {
+        if replace {
+            done!("{} waypoints removed", list.0.len());
+            *list = List::default();
         }
-        None => println!("'{}' is not assigned", name),
+        let (mut added, mut changed) = (0, 0);
+        for w in imported.0 {
+            let existing = match list.get_entry(&w.name) {
+                Some(e) if e.path == w.path && e.group == w.group => continue,
+                Some(e) => e.clone(),
+                None => {
+                    match list.add_entry(w.clone()) {
+                        Ok(()) => {
+                            done!("'{}' added to waypoints", w.name);
+                            added += 1
+                        }
+                        Err(e) => println!("'{}' skipped: {}", w.name, e),
+                    }
+                    continue;
+                }
+            };
+            match conflict {
+                Conflict::Skip => println!(
+                    "'{}' skipped, already assigned to {}",
+                    w.name,
+                    existing.path.display()
+                ),
+                Conflict::Rename => {
+                    let name = free_name(&w.name, &w.expanded_path(), list, &[]);
+                    list.add_entry(w.rename(&name))?;
+                    done!("'{}' added to waypoints as '{}'", w.name, name);
+                    added += 1
+                }
+                Conflict::Overwrite => {
+                    list.repath_entry(&w.name, &w.path)?;
+                    match (&w.group, &existing.group) {
+                        (Some(g), _) => drop(list.regroup_entry(&w.name, g)?),
+                        (None, Some(_)) => drop(list.ungroup_entry(&w.name)?),
+                        (None, None) => (),
+                    }
+                    done!("'{}' overwritten", w.name);
+                    changed += 1
+                }
+            }
+        }
+        done!("{} waypoints added, {} overwritten", added, changed);
+        Ok(())
+    })
+}
+
+/// `name`, or a variant of it which is neither in `list` nor in `proposed`
+///
+/// Tries prefixing the parent folder of `dir` first, then numbering.
+fn free_name(name: &str, dir: &Path, list: &List, proposed: &[Waypoint]) -> String {
+    let taken = |n: &str| list.get_entry(n).is_some() || proposed.iter().any(|w| w.name == n);
+    if !taken(name) {
+        return name.to_string();
     }
+    if let Some(parent) = dir.parent().and_then(Path::file_name) {
+        let prefixed = format!("{}-{}", parent.to_string_lossy(), name);
+        if !taken(&prefixed) {
+            return prefixed;
+        }
+    }
+    (2..)
+        .map(|i| format!("{}-{}", name, i))
+        .find(|n| !taken(n))
+        .unwrap_or_default()
 }
 
-pub fn parse_name(name: Option<&str>) -> String {
-    name.unwrap_or(&Filesystem::current_dir_name()).to_string()
+/// `name`, defaulting to the folder name of `dir`
+pub fn parse_name(name: Option<&str>, dir: &Path) -> Result<String> {
+    match name {
+        Some(n) => Ok(n.to_string()),
+        None => Filesystem::dir_name(dir),
+    }
 }
@@ -0,0 +1,81 @@
+use tele::waypoints::RESERVED_NAMES;
+use tele::List;
+use std::fs;
+
+/// Reserved names which are not offered as subcommands
+const HIDDEN: &[&str] = &["complete", "help", "-"];
+const GROUP_FLAGS: &[&str] = &["-g", "--group", "-d", "--dissolve"];
+
+/// Completion candidates for the last of `words`
+///
+/// `words` are the arguments typed after `tele` (or its shell function),
+/// the last one being the word under the cursor, possibly empty.
+pub fn candidates(list: &List, words: &[&str]) -> Vec<String> {
+    let (current, previous) = match words.split_last() {
+        Some((c, p)) => (*c, p),
+        None => ("", &[][..]),
+    };
+    let wants_groups = match previous.first() {
+        // `rm -g` and `rm -d` accept several groups
+        Some(&"rm") => matches!(
+            previous.iter().rev().find(|w| w.starts_with('-')),
+            Some(f) if GROUP_FLAGS.contains(f)
+        ),
+        // `add <name> <group>`
+        Some(&"add") if previous.len() == 2 && !previous[1].starts_with('-') => true,
+        _ => matches!(previous.last(), Some(f) if GROUP_FLAGS.contains(f)),
+    };
+
+    let mut out: Vec<String> = if wants_groups {
+        list.groups()
+    } else {
+        match previous.first() {
+            None if current.contains('/') => return sub_dirs(list, current),
+            None => list
+                .0
+                .iter()
+                .map(|w| w.name.clone())
+                .chain(
+                    RESERVED_NAMES
+                        .iter()
+                        .filter(|s| !HIDDEN.contains(s))
+                        .map(|s| s.to_string()),
+                )
+                .collect(),
+            Some(&"rm") => list.0.iter().map(|w| w.name.clone()).collect(),
+            _ => Vec::new(),
+        }
+    };
+    out.retain(|c| c.starts_with(current));
+    out.sort();
+    out.dedup();
+    out
+}
+
+/// Directories below a waypoint for a `name/sub/dir` word
+fn sub_dirs(list: &List, current: &str) -> Vec<String> {
+    let (typed, partial) = match current.rfind('/') {
+        Some(i) => (&current[..=i], &current[i + 1..]),
+        None => return Vec::new(),
+    };
+    let mut parts = typed.splitn(2, '/');
+    let name = parts.next().unwrap_or_default();
+    let rest = parts.next().unwrap_or_default();
+    let dir = match list.get_entry(name) {
+        Some(w) => w.expanded_path().join(rest),
+        None => return Vec::new(),
+    };
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+    let mut out: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|n| n.starts_with(partial) && (partial.starts_with('.') || !n.starts_with('.')))
+        .map(|n| format!("{}{}/", typed, n))
+        .collect();
+    out.sort();
+    out
+}
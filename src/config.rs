@@ -0,0 +1,17 @@
+use tele::{ListSort, ListView, TELE};
+
+/// Sorting applied whenever the list is saved
+pub fn default_sort() -> ListSort {
+    match &TELE.config().get("default-sort") {
+        Some(s) => ListSort::parse(s),
+        None => ListSort::Path,
+    }
+}
+
+/// View used by `list` without flags
+pub fn default_view() -> ListView {
+    match &TELE.config().get("default-view") {
+        Some(s) => ListView::parse(s),
+        None => ListView::Groupless,
+    }
+}
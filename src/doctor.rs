@@ -0,0 +1,167 @@
+use crate::prompt;
+use std::path::{Path, PathBuf};
+use tele::{paths, validate_name, walk, Error, List, PathStatus};
+
+/// How problems found by [`check`] are handled
+pub enum Mode {
+    /// Only report problems
+    Report,
+    /// Ask what to do about each problem
+    Interactive,
+    /// Relocate moved directories, remove the ones that cannot be found
+    Fix,
+}
+
+/// Where to look for directories which have moved
+pub struct Search {
+    pub root: PathBuf,
+    pub depth: usize,
+}
+
+/// Checks every waypoint against the filesystem, applying fixes to `list`
+///
+/// Returns the number of problems left unresolved.
+pub fn check(list: &mut List, mode: &Mode, search: &Search) -> usize {
+    let mut unresolved = 0;
+    let problems: Vec<(String, PathBuf, PathStatus)> = list
+        .0
+        .iter()
+        .map(|w| (w.name.clone(), w.path.clone(), w.status()))
+        .filter(|(_, _, s)| s.is_problem())
+        .collect();
+    for (name, path, status) in &problems {
+        report(mode, &format!("'{}' -> {}: {}", name, path.display(), status.describe()));
+        let changed = match mode {
+            Mode::Report => false,
+            Mode::Fix => fix(list, name, path, *status, search),
+            Mode::Interactive => ask(list, name, path, search),
+        };
+        if !changed {
+            unresolved += 1
+        }
+    }
+
+    let duplicates: Vec<Vec<String>> = list
+        .duplicates()
+        .iter()
+        .map(|ws| ws.iter().map(|w| w.name.clone()).collect())
+        .collect();
+    for names in &duplicates {
+        let path = list.get_entry(&names[0]).map(|w| w.path.clone()).unwrap_or_default();
+        report(mode, &format!("'{}' share the same path: {}", names.join("', '"), path.display()));
+        let mut kept = names.len();
+        if let Mode::Interactive = mode {
+            for name in &names[1..] {
+                if prompt::confirm(&format!("remove '{}'?", name)) && remove(list, name) {
+                    kept -= 1
+                }
+            }
+        }
+        if kept > 1 {
+            unresolved += 1
+        }
+    }
+
+    let invalid: Vec<Error> = list.0.iter().filter_map(|w| validate_name(&w.name).err()).collect();
+    unresolved += invalid.len();
+    for e in &invalid {
+        println!("{}", e);
+        if let Error::InvalidName { name, .. } = e {
+            println!(" rename it with `tele --name <new name> -- '{}'`", name)
+        }
+    }
+
+    if problems.is_empty() && duplicates.is_empty() && invalid.is_empty() {
+        println!("all {} waypoints are valid", list.0.len())
+    }
+    unresolved
+}
+
+/// Prints a problem, on stderr with the questions about it when interactive
+///
+/// The shell integration captures stdout, which would otherwise only show
+/// up once every question has been answered.
+fn report(mode: &Mode, line: &str) {
+    match mode {
+        Mode::Interactive => eprintln!("{}", line),
+        _ => println!("{}", line),
+    }
+}
+
+fn fix(list: &mut List, name: &str, path: &Path, status: PathStatus, search: &Search) -> bool {
+    if status != PathStatus::Missing {
+        println!(" left as is");
+        return false;
+    }
+    let found = find_moved(path, search);
+    match found.as_slice() {
+        [] => remove(list, name),
+        [p] => repath(list, name, p),
+        _ => {
+            println!(" several candidates found, left as is:");
+            for p in &found {
+                println!("  {}", p.display())
+            }
+            false
+        }
+    }
+}
+
+fn ask(list: &mut List, name: &str, path: &Path, search: &Search) -> bool {
+    loop {
+        let answer = prompt::ask(" [r]emove, [p]ath, [s]earch or [k]eep?").unwrap_or_default();
+        match answer.as_str() {
+            "r" => return remove(list, name),
+            "p" => {
+                let new_path = prompt::ask(" new path:").unwrap_or_default();
+                match Path::new(&new_path).canonicalize() {
+                    Ok(p) if p.is_dir() => return repath(list, name, &p),
+                    _ => eprintln!(" '{}' is not a directory", new_path),
+                }
+            }
+            "s" => {
+                let found = find_moved(path, search);
+                if found.is_empty() {
+                    eprintln!(" no directory named like it below {}", search.root.display());
+                    continue;
+                }
+                for (i, p) in found.iter().enumerate() {
+                    eprintln!(" {}) {}", i + 1, p.display())
+                }
+                let pick = prompt::ask(" number to repath to (empty to go back):").unwrap_or_default();
+                if let Some(p) = pick.parse::<usize>().ok().and_then(|i| found.get(i.wrapping_sub(1))) {
+                    return repath(list, name, p);
+                }
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// Directories below the search root sharing the missing directory's name
+fn find_moved(path: &Path, search: &Search) -> Vec<PathBuf> {
+    match paths::expand(path).file_name() {
+        Some(n) => walk::find_named(&search.root, n, search.depth),
+        None => Vec::new(),
+    }
+}
+
+fn remove(list: &mut List, name: &str) -> bool {
+    match list.remove_entries(&[name]) {
+        Ok(_) => {
+            println!(" '{}' removed from waypoints", name);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn repath(list: &mut List, name: &str, path: &Path) -> bool {
+    match list.repath_entry(name, &paths::contract(path)) {
+        Ok(_) => {
+            println!(" '{}' now points to {}", name, path.display());
+            true
+        }
+        Err(_) => false,
+    }
+}
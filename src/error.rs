@@ -0,0 +1,101 @@
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors surfaced to the user
+///
+/// Each variant maps to a distinct process exit code, see [`Error::exit_code`].
+/// Exit code 2 is reserved for a successful teleport.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("'{0}' is not a waypoint")]
+    NotFound(String),
+    #[error("'{0}' matches several waypoints")]
+    Ambiguous(String),
+    #[error("'{name}' is not a valid waypoint name: {reason}")]
+    InvalidName { name: String, reason: &'static str },
+    #[error("'{name}' is already assigned to: {}", .path.display())]
+    AlreadyExists { name: String, path: PathBuf },
+    #[error("'{0}' is not a valid group name")]
+    InvalidGroup(String),
+    #[error("group '{0}' is not defined")]
+    GroupNotFound(String),
+    #[error("'{0}' does not have a group")]
+    NoGroup(String),
+    #[error("history does not go {0} that far")]
+    EndOfHistory(&'static str),
+    #[error("'{}' has no name to use, pass one explicitly", .0.display())]
+    NoName(PathBuf),
+    #[error("'{}' is not a directory", .0.display())]
+    NotADirectory(PathBuf),
+    #[error("'{0}' is not a valid function name")]
+    InvalidFunctionName(String),
+    #[error("could not update config: {0}")]
+    Config(String),
+    #[error("{}: {source}", .path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("{} is not a {format} database", .path.display())]
+    BadImport { path: PathBuf, format: &'static str },
+    #[error("{}:{line}: {message}", .path.display())]
+    Syntax {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    #[error("could not parse {}: {source}", .path.display())]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("{0} problems left unresolved")]
+    Unresolved(usize),
+    #[error("not asking to {0} without a terminal, pass --yes to confirm")]
+    NeedsConfirmation(String),
+    #[error("cancelled, no changes made")]
+    Cancelled,
+}
+
+impl Error {
+    /// Process exit code
+    ///
+    /// | code | meaning                             |
+    /// |------|-------------------------------------|
+    /// | 1    | invalid input                       |
+    /// | 2    | success, stdout holds a path to cd  |
+    /// | 3    | waypoint not found                  |
+    /// | 4    | query matches several waypoints     |
+    /// | 5    | waypoint already exists             |
+    /// | 6    | group not found                     |
+    /// | 7    | filesystem or config error          |
+    /// | 8    | waypoint file could not be parsed   |
+    /// | 9    | `doctor` left problems unresolved   |
+    /// | 10   | change not confirmed                |
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::NoGroup(_)
+            | Self::EndOfHistory(_)
+            | Self::NoName(_)
+            | Self::InvalidName { .. }
+            | Self::InvalidGroup(_)
+            | Self::NotADirectory(_)
+            | Self::InvalidFunctionName(_) => 1,
+            Self::NotFound(_) => 3,
+            Self::Ambiguous(_) => 4,
+            Self::AlreadyExists { .. } => 5,
+            Self::GroupNotFound(_) => 6,
+            Self::Config(_) | Self::Io { .. } => 7,
+            Self::Parse { .. } | Self::Syntax { .. } | Self::BadImport { .. } => 8,
+            Self::Unresolved(_) => 9,
+            Self::NeedsConfirmation(_) | Self::Cancelled => 10,
+        }
+    }
+
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Self::Io {
+            path: path.into(),
+            source,
+        }
+    }
+}
@@ -1,32 +1,34 @@
 use std::env;
-use std::path::PathBuf;
-use crate::TELE;
+use std::io;
+use std::path::{Path, PathBuf};
+use tele::{paths, Error, Result};
 
 /// Filesystem helpers
 pub struct Filesystem();
 
 impl Filesystem {
-    /// Returns the current working directory as a String
-    pub fn current_dir() -> String {
-        env::current_dir()
-            .expect("invalid working directory")
-            .into_os_string()
-            .into_string()
-            .expect("cannot parse working directory")
+    /// Returns the current working directory
+    pub fn current_dir() -> Result<PathBuf> {
+        env::current_dir().map_err(|e| Error::io("working directory", e))
     }
-    /// Returns the current working folder name as a String
-    pub fn current_dir_name() -> String {
-        let c = env::current_dir().expect("invalid working directory");
-        c.file_name()
-            .expect("invalid directory name")
-            .to_str()
-            .expect("cannot parse working directory")
-            .to_string()
+    /// Returns the folder name of `dir`, replacing invalid UTF-8
+    pub fn dir_name(dir: &Path) -> Result<String> {
+        match dir.file_name() {
+            Some(name) => Ok(name.to_string_lossy().into_owned()),
+            None => Err(Error::NoName(dir.to_path_buf())),
+        }
     }
-
-    /// PathBuf for `waypoints.json`
-    /// ~/.config/tele/waypoints.json
-    pub fn waypoints_file() -> PathBuf {
-        TELE.config_dir().join("waypoints.json")
+    /// Resolves a directory given on the command line
+    ///
+    /// `~` and environment variables are expanded, relative paths are taken
+    /// from the working directory and symlinks are resolved.
+    pub fn resolve_dir(dir: &Path) -> Result<PathBuf> {
+        let dir = Self::current_dir()?.join(paths::expand(dir));
+        match dir.canonicalize() {
+            Ok(d) if d.is_dir() => Ok(d),
+            Ok(d) => Err(Error::NotADirectory(d)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(Error::NotADirectory(dir)),
+            Err(e) => Err(Error::io(dir, e)),
+        }
     }
 }
@@ -0,0 +1,98 @@
+use std::io::{self, Write};
+use std::path::Path;
+use tele::List;
+
+/// Output formats of `list`
+pub enum Format {
+    Table,
+    Json,
+    Tsv,
+    Csv,
+    Names,
+    Paths,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "json" => Self::Json,
+            "tsv" => Self::Tsv,
+            "csv" => Self::Csv,
+            "names" => Self::Names,
+            "paths" => Self::Paths,
+            _ => Self::Table,
+        }
+    }
+}
+
+/// Prints `list` in a machine-readable `format`
+///
+/// Paths are printed expanded, ready to `cd` into. `tsv` prints `name`,
+/// `group` and `path` columns without a header, escaping tabs, newlines and
+/// backslashes; `csv` includes a header row.
+pub fn print(list: &List, format: &Format) -> io::Result<()> {
+    let list = List(list.0.iter().map(|w| w.repath(&w.expanded_path())).collect());
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match format {
+        Format::Table => (),
+        Format::Json => writeln!(
+            out,
+            "{}",
+            serde_json::to_string_pretty(&list).expect("could not serialize list")
+        )?,
+        Format::Tsv => {
+            for w in &list.0 {
+                let group = w.group.as_deref().unwrap_or_default();
+                writeln!(out, "{}\t{}\t{}", tsv(&w.name), tsv(group), tsv(&w.path.to_string_lossy()))?
+            }
+        }
+        Format::Csv => {
+            writeln!(out, "name,group,path")?;
+            for w in &list.0 {
+                let group = w.group.as_deref().unwrap_or_default();
+                writeln!(out, "{},{},{}", csv(&w.name), csv(group), csv(&w.path.to_string_lossy()))?
+            }
+        }
+        Format::Names => {
+            for w in &list.0 {
+                writeln!(out, "{}", w.name)?
+            }
+        }
+        Format::Paths => {
+            for w in &list.0 {
+                write_path(&mut out, &w.path)?;
+                writeln!(out)?
+            }
+        }
+    }
+    out.flush()
+}
+
+fn tsv(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn csv(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Writes `path` byte for byte, even if it is not valid UTF-8
+#[cfg(unix)]
+pub fn write_path(out: &mut impl Write, path: &Path) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    out.write_all(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+pub fn write_path(out: &mut impl Write, path: &Path) -> io::Result<()> {
+    write!(out, "{}", path.display())
+}
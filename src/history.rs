@@ -0,0 +1,91 @@
+use crate::error::{Error, Result};
+use crate::storage;
+use crate::TELE;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of directories remembered
+const MAX_ENTRIES: usize = 100;
+
+/// Directories teleported between, oldest first
+///
+/// Works like a browser history: [`History::back`] and [`History::forward`]
+/// move a cursor, while recording a new teleport drops everything after it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct History {
+    #[serde(with = "crate::paths::lossless_vec")]
+    pub entries: Vec<PathBuf>,
+    /// Index of the current directory in `entries`
+    pub cursor: usize,
+}
+
+impl History {
+    /// ~/.config/tele/history.json
+    pub fn default_path() -> PathBuf {
+        TELE.config_dir().join("history.json")
+    }
+
+    /// Reads the history, returning an empty one if nothing has been saved yet
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(Error::io(path, e)),
+        };
+        let mut history: Self = serde_json::from_str(&text).map_err(|source| Error::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        history.cursor = history.cursor.min(history.entries.len().saturating_sub(1));
+        Ok(history)
+    }
+
+    /// Writes the history, see [`FileStore`](crate::FileStore) for locking
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).expect("could not serialize input");
+        storage::write_atomic(path, json.as_bytes())
+    }
+
+    /// Records a teleport from `from` to `to`
+    pub fn record(&mut self, from: &Path, to: &Path) {
+        self.entries.truncate(self.cursor + 1);
+        for dir in &[from, to] {
+            if self.entries.last().map(PathBuf::as_path) != Some(dir) {
+                self.entries.push(dir.to_path_buf())
+            }
+        }
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+        self.cursor = self.entries.len().saturating_sub(1);
+    }
+
+    /// Moves `n` directories back, returning the directory reached
+    pub fn back(&mut self, n: usize) -> Option<&Path> {
+        if n == 0 || n > self.cursor {
+            return None;
+        }
+        self.cursor -= n;
+        Some(&self.entries[self.cursor])
+    }
+
+    /// Moves `n` directories forward, returning the directory reached
+    pub fn forward(&mut self, n: usize) -> Option<&Path> {
+        if n == 0 || self.cursor + n >= self.entries.len() {
+            return None;
+        }
+        self.cursor += n;
+        Some(&self.entries[self.cursor])
+    }
+
+    /// Most recent directory up to the cursor other than `current`, like `cd -`
+    pub fn previous(&self, current: &Path) -> Option<&Path> {
+        self.entries
+            .iter()
+            .take(self.cursor + 1)
+            .rev()
+            .map(PathBuf::as_path)
+            .find(|e| *e != current)
+    }
+}
@@ -0,0 +1,263 @@
+use crate::paths;
+use std::convert::TryInto;
+use std::env;
+use std::path::PathBuf;
+use std::str::{self, FromStr};
+
+/// Directory jumpers whose databases can be imported
+#[derive(Clone, Copy, Debug)]
+pub enum Source {
+    Zoxide,
+    Autojump,
+    Z,
+    Fasd,
+    Bashmarks,
+}
+
+impl Source {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "zoxide" => Some(Self::Zoxide),
+            "autojump" => Some(Self::Autojump),
+            "z" => Some(Self::Z),
+            "fasd" => Some(Self::Fasd),
+            "bashmarks" => Some(Self::Bashmarks),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Zoxide => "zoxide",
+            Self::Autojump => "autojump",
+            Self::Z => "z",
+            Self::Fasd => "fasd",
+            Self::Bashmarks => "bashmarks",
+        }
+    }
+}
+
+/// A directory read from another tool's database
+#[derive(Clone, Debug)]
+pub struct Entry {
+    /// The directory as a real path, not in the stored form
+    pub path: PathBuf,
+    /// Name given by the tool, only bashmarks has one
+    pub name: Option<String>,
+    /// The tool's score, roughly the number of visits
+    pub rank: f64,
+    /// Unix timestamp of the last visit, if known
+    pub last_visit: Option<u64>,
+}
+
+/// Reads the entries of a database written by `source`
+///
+/// Lines which cannot be understood are skipped. Returns `None` if the
+/// data is not in the expected format at all.
+pub fn parse(source: Source, data: &[u8]) -> Option<Vec<Entry>> {
+    match source {
+        Source::Zoxide if data.starts_with(&ZOXIDE_VERSION.to_le_bytes()) => parse_zoxide(&data[4..]),
+        Source::Zoxide => Some(lines(data).filter_map(parse_zoxide_query).collect()),
+        Source::Autojump => Some(lines(data).filter_map(parse_autojump).collect()),
+        Source::Z | Source::Fasd => Some(lines(data).filter_map(parse_z).collect()),
+        Source::Bashmarks => Some(lines(data).filter_map(parse_bashmarks).collect()),
+    }
+}
+
+/// Lines are kept as bytes, as paths need not be valid UTF-8
+fn lines(data: &[u8]) -> impl Iterator<Item = &[u8]> + '_ {
+    data.split(|&b| b == b'\n')
+        .map(|l| l.strip_suffix(b"\r").unwrap_or(l))
+        .filter(|l| !trim(l).is_empty())
+}
+
+fn trim_start(s: &[u8]) -> &[u8] {
+    let start = s.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(s.len());
+    &s[start..]
+}
+
+fn trim(s: &[u8]) -> &[u8] {
+    let s = trim_start(s);
+    let end = s.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(0, |i| i + 1);
+    &s[..end]
+}
+
+fn split_once(s: &[u8], sep: u8) -> Option<(&[u8], &[u8])> {
+    let i = s.iter().position(|&b| b == sep)?;
+    Some((&s[..i], &s[i + 1..]))
+}
+
+fn number<T: FromStr>(s: &[u8]) -> Option<T> {
+    str::from_utf8(s).ok()?.parse().ok()
+}
+
+fn entry(path: &[u8], rank: f64, last_visit: Option<u64>) -> Option<Entry> {
+    if path.is_empty() {
+        return None;
+    }
+    Some(Entry {
+        path: PathBuf::from(paths::from_bytes(path.to_vec())),
+        name: None,
+        rank,
+        last_visit,
+    })
+}
+
+/// Version header of zoxide's `db.zo`
+const ZOXIDE_VERSION: u32 = 3;
+
+/// zoxide's `db.zo`: a bincode encoded list of `(path, rank, last_accessed)`
+fn parse_zoxide(data: &[u8]) -> Option<Vec<Entry>> {
+    let mut reader = Reader(data);
+    let len = reader.u64()?;
+    let mut entries = Vec::new();
+    for _ in 0..len {
+        let path_len = reader.u64()? as usize;
+        let path = reader.take(path_len)?;
+        let rank = f64::from_le_bytes(reader.take(8)?.try_into().ok()?);
+        let last_accessed = reader.u64()?;
+        entries.extend(entry(path, rank, Some(last_accessed)));
+    }
+    Some(entries)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+}
+
+/// Output of `zoxide query --list --score`: `<rank> <path>`
+fn parse_zoxide_query(line: &[u8]) -> Option<Entry> {
+    let (rank, path) = split_once(trim_start(line), b' ')?;
+    entry(trim_start(path), number(rank)?, None)
+}
+
+/// autojump's `autojump.txt`: `<weight>\t<path>`
+fn parse_autojump(line: &[u8]) -> Option<Entry> {
+    let (weight, path) = split_once(line, b'\t')?;
+    entry(path, number(weight)?, None)
+}
+
+/// z's `~/.z` and fasd's `~/.fasd`: `<path>|<rank>|<timestamp>`
+fn parse_z(line: &[u8]) -> Option<Entry> {
+    let mut fields = line.rsplitn(3, |&b| b == b'|');
+    let time = number(fields.next()?)?;
+    let rank = number(fields.next()?)?;
+    entry(fields.next()?, rank, Some(time))
+}
+
+/// bashmarks' `~/.sdirs`: `export DIR_<name>="<path>"`
+fn parse_bashmarks(line: &[u8]) -> Option<Entry> {
+    let (name, path) = split_once(trim(line).strip_prefix(b"export DIR_")?, b'=')?;
+    let quoted = |b: &u8| *b == b'"' || *b == b'\'';
+    let start = path.iter().position(|b| !quoted(b)).unwrap_or(path.len());
+    let end = path.iter().rposition(|b| !quoted(b)).map_or(start, |i| i + 1);
+    let path = &path[start..end];
+    // bashmarks stores the home directory as `$HOME`
+    let path = match (path.strip_prefix(b"$HOME"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => [&paths::to_bytes(&home)[..], rest].concat(),
+        _ => path.to_vec(),
+    };
+    let mut e = entry(&path, 1.0, None)?;
+    e.name = Some(str::from_utf8(name).ok()?.to_string());
+    Some(e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn zoxide_db(entries: &[(&str, f64, u64)]) -> Vec<u8> {
+        let mut data = ZOXIDE_VERSION.to_le_bytes().to_vec();
+        data.extend(&(entries.len() as u64).to_le_bytes());
+        for (path, rank, time) in entries {
+            data.extend(&(path.len() as u64).to_le_bytes());
+            data.extend(path.as_bytes());
+            data.extend(&rank.to_le_bytes());
+            data.extend(&time.to_le_bytes());
+        }
+        data
+    }
+
+    fn paths(entries: &[Entry]) -> Vec<&Path> {
+        entries.iter().map(|e| e.path.as_path()).collect()
+    }
+
+    #[test]
+    fn zoxide_database() {
+        let data = zoxide_db(&[("/src/a", 4.5, 1_600_000_000), ("/src/b", 1.0, 7)]);
+        let entries = parse(Source::Zoxide, &data).unwrap();
+        assert_eq!(paths(&entries), [Path::new("/src/a"), Path::new("/src/b")]);
+        assert_eq!(entries[0].rank, 4.5);
+        assert_eq!(entries[0].last_visit, Some(1_600_000_000));
+    }
+
+    #[test]
+    fn truncated_zoxide_database() {
+        let data = zoxide_db(&[("/src/a", 4.5, 1_600_000_000)]);
+        assert!(parse(Source::Zoxide, &data[..data.len() - 3]).is_none());
+        assert!(parse(Source::Zoxide, &data[..6]).is_none());
+    }
+
+    #[test]
+    fn zoxide_query_output() {
+        let entries = parse(Source::Zoxide, b"  12.5 /src/a\nnot a score\n  1 /src/with space\n").unwrap();
+        assert_eq!(paths(&entries), [Path::new("/src/a"), Path::new("/src/with space")]);
+        assert_eq!(entries[0].rank, 12.5);
+    }
+
+    #[test]
+    fn autojump() {
+        let entries = parse(Source::Autojump, b"10.0\t/src/a\r\nbroken line\nx\t/src/b\n22.4\t/src/c\n").unwrap();
+        assert_eq!(paths(&entries), [Path::new("/src/a"), Path::new("/src/c")]);
+        assert_eq!(entries[1].rank, 22.4);
+    }
+
+    #[test]
+    fn z_and_fasd() {
+        let data = b"/src/a|3|1600000000\n/src/pipe|d|2|5\n/src/b|x|1\n|1|1\n";
+        for source in &[Source::Z, Source::Fasd] {
+            let entries = parse(*source, data).unwrap();
+            assert_eq!(paths(&entries), [Path::new("/src/a"), Path::new("/src/pipe|d")]);
+            assert_eq!(entries[0].last_visit, Some(1_600_000_000));
+        }
+    }
+
+    #[test]
+    fn bashmarks() {
+        let data = b"export DIR_web=\"$HOME/src/web\"\nexport DIR_tmp='/tmp'\nalias x=y\n";
+        let entries = parse(Source::Bashmarks, data).unwrap();
+        let web = match env::var_os("HOME") {
+            Some(mut home) => {
+                home.push("/src/web");
+                PathBuf::from(home)
+            }
+            None => PathBuf::from("$HOME/src/web"),
+        };
+        assert_eq!(paths(&entries), [web.as_path(), Path::new("/tmp")]);
+        assert_eq!(entries[0].name.as_deref(), Some("web"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn paths_need_not_be_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+        let entries = parse(Source::Autojump, b"2.0\t/src/caf\xe9\n").unwrap();
+        assert_eq!(entries[0].path.as_os_str().as_bytes(), b"/src/caf\xe9");
+        let entries = parse(Source::Z, b"/src/\xff|1|2\n").unwrap();
+        assert_eq!(entries[0].path.as_os_str().as_bytes(), b"/src/\xff");
+    }
+}
@@ -0,0 +1,308 @@
+use crate::error::{Error, Result};
+use crate::waypoints::{List, Waypoint};
+use crate::TELE;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A line of the journal
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Entry {
+    pub id: u64,
+    /// Unix timestamp
+    pub time: u64,
+    #[serde(flatten)]
+    pub kind: Kind,
+}
+
+impl Entry {
+    /// Puts the waypoints of `list` back as they were before this change
+    pub fn revert(&self, list: &mut List) {
+        if let Kind::Change { before, after, .. } = &self.kind {
+            apply(list, after, before)
+        }
+    }
+
+    /// Makes this change to `list` again
+    pub fn reapply(&self, list: &mut List) {
+        if let Kind::Change { before, after, .. } = &self.kind {
+            apply(list, before, after)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Kind {
+    /// Waypoints as they were before and after a command, only those which
+    /// changed are kept
+    Change {
+        command: String,
+        before: Vec<Waypoint>,
+        after: Vec<Waypoint>,
+    },
+    /// The change with id `target` was reverted
+    Undo { target: u64 },
+    /// The change with id `target` was applied again
+    Redo { target: u64 },
+    /// The list was replaced wholesale, e.g. from a backup
+    Reset { command: String },
+}
+
+/// Append-only log of changes to the waypoint list
+///
+/// Undoing or redoing a change appends a line as well, so the file is never
+/// rewritten. Writers should hold the store's lock and record a change only
+/// once the list is saved.
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// ~/.config/tele/journal.jsonl
+    pub fn open_default() -> Self {
+        Self::new(TELE.config_dir().join("journal.jsonl"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads every entry, skipping lines which cannot be parsed
+    pub fn load(&self) -> Result<Vec<Entry>> {
+        match fs::read_to_string(&self.path) {
+            Ok(text) => Ok(text.lines().filter_map(|l| serde_json::from_str(l).ok()).collect()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(Error::io(&self.path, e)),
+        }
+    }
+
+    /// Records the difference between `before` and `after` made by `command`
+    ///
+    /// Nothing is recorded if no waypoint changed.
+    pub fn record(&self, command: &str, before: &List, after: &List) -> Result<()> {
+        let (old, new) = diff(before, after);
+        if old.is_empty() && new.is_empty() {
+            return Ok(());
+        }
+        self.append(Kind::Change {
+            command: command.to_string(),
+            before: old,
+            after: new,
+        })
+    }
+
+    /// The change [`Journal::undo`] would revert
+    pub fn next_undo(&self) -> Result<Option<Entry>> {
+        let entries = self.load()?;
+        let (done, _) = stacks(&entries);
+        Ok(done.last().and_then(|id| entries.into_iter().find(|e| e.id == *id)))
+    }
+
+    /// The change [`Journal::redo`] would apply
+    pub fn next_redo(&self) -> Result<Option<Entry>> {
+        let entries = self.load()?;
+        let (_, undone) = stacks(&entries);
+        Ok(undone.last().and_then(|id| entries.into_iter().find(|e| e.id == *id)))
+    }
+
+    /// Records that `entry` was reverted, after saving the reverted list
+    pub fn record_undo(&self, entry: &Entry) -> Result<()> {
+        self.append(Kind::Undo { target: entry.id })
+    }
+
+    /// Records that `entry` was applied again, after saving the list
+    pub fn record_redo(&self, entry: &Entry) -> Result<()> {
+        self.append(Kind::Redo { target: entry.id })
+    }
+
+    /// Records that the list was replaced without knowing what it held
+    /// before, so earlier changes can no longer be undone or redone
+    pub fn record_reset(&self, command: &str) -> Result<()> {
+        self.append(Kind::Reset {
+            command: command.to_string(),
+        })
+    }
+
+    /// Recorded changes and resets, oldest first, paired with whether they
+    /// are currently undone
+    pub fn changes(&self) -> Result<Vec<(Entry, bool)>> {
+        let entries = self.load()?;
+        let mut undone = Vec::new();
+        for e in &entries {
+            match e.kind {
+                Kind::Undo { target } => undone.push(target),
+                Kind::Redo { target } => undone.retain(|id| *id != target),
+                _ => (),
+            }
+        }
+        Ok(entries
+            .into_iter()
+            .filter(|e| matches!(e.kind, Kind::Change { .. } | Kind::Reset { .. }))
+            .map(|e| {
+                let is_undone = undone.contains(&e.id);
+                (e, is_undone)
+            })
+            .collect())
+    }
+
+    fn append(&self, kind: Kind) -> Result<()> {
+        let id = self.load()?.last().map_or(1, |e| e.id + 1);
+        let entry = Entry {
+            id,
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            kind,
+        };
+        let line = serde_json::to_string(&entry).expect("could not serialize journal entry");
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut f| writeln!(f, "{}", line))
+            .map_err(|e| Error::io(&self.path, e))
+    }
+}
+
+/// Waypoints only in `before` and those only in `after`
+pub fn diff(before: &List, after: &List) -> (Vec<Waypoint>, Vec<Waypoint>) {
+    let only = |a: &List, b: &List| a.0.iter().filter(|w| !b.0.contains(w)).cloned().collect();
+    (only(before, after), only(after, before))
+}
+
+/// Ids of the changes which are in effect and of those which were undone
+/// and can be redone, most recent last
+fn stacks(entries: &[Entry]) -> (Vec<u64>, Vec<u64>) {
+    let (mut done, mut undone) = (Vec::new(), Vec::new());
+    for e in entries {
+        match e.kind {
+            Kind::Change { .. } => {
+                done.push(e.id);
+                undone.clear();
+            }
+            Kind::Undo { target } => {
+                done.retain(|id| *id != target);
+                undone.push(target);
+            }
+            Kind::Redo { target } => {
+                undone.retain(|id| *id != target);
+                done.push(target);
+            }
+            Kind::Reset { .. } => {
+                done.clear();
+                undone.clear();
+            }
+        }
+    }
+    (done, undone)
+}
+
+/// Replaces the waypoints in `remove` by those in `add`
+fn apply(list: &mut List, remove: &[Waypoint], add: &[Waypoint]) {
+    list.0
+        .retain(|w| !remove.iter().chain(add).any(|r| r.name == w.name));
+    list.0.extend(add.iter().cloned());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn journal(test: &str) -> Journal {
+        let path = env::temp_dir().join(format!("tele-journal-{}-{}.jsonl", std::process::id(), test));
+        let _ = fs::remove_file(&path);
+        Journal::new(path)
+    }
+
+    fn list(waypoints: &[(&str, &str)]) -> List {
+        List(waypoints.iter().map(|(n, p)| Waypoint::new(n, Path::new(p), None)).collect())
+    }
+
+    fn ids(entry: Result<Option<Entry>>) -> Option<u64> {
+        entry.unwrap().map(|e| e.id)
+    }
+
+    #[test]
+    fn undo_then_redo() {
+        let j = journal("undo-redo");
+        j.record("add a", &list(&[]), &list(&[("a", "/a")])).unwrap();
+        j.record("add b", &list(&[("a", "/a")]), &list(&[("a", "/a"), ("b", "/b")])).unwrap();
+        assert_eq!(ids(j.next_undo()), Some(2));
+        assert_eq!(ids(j.next_redo()), None);
+
+        let last = j.next_undo().unwrap().unwrap();
+        let mut current = list(&[("a", "/a"), ("b", "/b")]);
+        last.revert(&mut current);
+        assert_eq!(current, list(&[("a", "/a")]));
+        j.record_undo(&last).unwrap();
+        assert_eq!(ids(j.next_undo()), Some(1));
+        assert_eq!(ids(j.next_redo()), Some(2));
+        let undone: Vec<bool> = j.changes().unwrap().into_iter().map(|(_, u)| u).collect();
+        assert_eq!(undone, [false, true]);
+
+        last.reapply(&mut current);
+        assert_eq!(current, list(&[("a", "/a"), ("b", "/b")]));
+        j.record_redo(&last).unwrap();
+        assert_eq!(ids(j.next_undo()), Some(2));
+        assert_eq!(ids(j.next_redo()), None);
+        fs::remove_file(j.path()).unwrap();
+    }
+
+    #[test]
+    fn a_new_change_clears_the_redo_stack() {
+        let j = journal("new-change");
+        j.record("add a", &list(&[]), &list(&[("a", "/a")])).unwrap();
+        j.record_undo(&j.next_undo().unwrap().unwrap()).unwrap();
+        assert_eq!(ids(j.next_redo()), Some(1));
+        j.record("add b", &list(&[]), &list(&[("b", "/b")])).unwrap();
+        assert_eq!(ids(j.next_redo()), None);
+        assert_eq!(ids(j.next_undo()), Some(3));
+        fs::remove_file(j.path()).unwrap();
+    }
+
+    #[test]
+    fn a_reset_clears_both_stacks() {
+        let j = journal("reset");
+        j.record("add a", &list(&[]), &list(&[("a", "/a")])).unwrap();
+        j.record("add b", &list(&[("a", "/a")]), &list(&[("a", "/a"), ("b", "/b")])).unwrap();
+        j.record_undo(&j.next_undo().unwrap().unwrap()).unwrap();
+        j.record_reset("doctor --restore").unwrap();
+        assert_eq!(ids(j.next_undo()), None);
+        assert_eq!(ids(j.next_redo()), None);
+        let kinds: Vec<bool> = j
+            .changes()
+            .unwrap()
+            .iter()
+            .map(|(e, _)| matches!(e.kind, Kind::Reset { .. }))
+            .collect();
+        assert_eq!(kinds, [false, false, true]);
+        fs::remove_file(j.path()).unwrap();
+    }
+
+    #[test]
+    fn reverting_a_rename() {
+        let j = journal("rename");
+        j.record("--name b a", &list(&[("a", "/x")]), &list(&[("b", "/x")])).unwrap();
+        let rename = j.next_undo().unwrap().unwrap();
+
+        // a waypoint since added under the old name is replaced, not duplicated
+        let mut current = list(&[("b", "/x"), ("c", "/c"), ("a", "/elsewhere")]);
+        rename.revert(&mut current);
+        assert_eq!(current, list(&[("c", "/c"), ("a", "/x")]));
+        rename.reapply(&mut current);
+        assert_eq!(current, list(&[("c", "/c"), ("b", "/x")]));
+        fs::remove_file(j.path()).unwrap();
+    }
+}
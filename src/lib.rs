@@ -0,0 +1,22 @@
+//! Waypoint store shared by the `tele` binary and other tooling
+//!
+//! Nothing in this crate prints; callers decide how to report results.
+
+pub mod error;
+pub mod history;
+pub mod import;
+pub mod journal;
+pub mod paths;
+pub mod resolve;
+pub mod share;
+pub mod storage;
+pub mod walk;
+pub mod waypoints;
+
+pub use error::{Error, Result};
+pub use history::History;
+pub use storage::{FileStore, Store, StoreLock};
+pub use waypoints::{validate_name, List, ListSort, ListView, PathStatus, Waypoint};
+
+/// kettle app locating tele's config directory (`~/.config/tele`)
+pub const TELE: kettle::App = kettle::app("tele", None);
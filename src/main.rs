@@ -3,13 +3,23 @@ extern crate clap;
 
 mod cli;
 mod cmd;
+mod complete;
+mod config;
+mod doctor;
 mod filesystem;
+mod format;
+mod picker;
+mod prompt;
+mod shell;
 mod table;
-mod waypoints;
-
-pub(crate) const TELE: kettle::App = kettle::app("tele", None);
+mod term;
 
 fn main() {
+    term::init_color(cli::color_arg().as_deref());
     let matches = cli::parse_args();
-    cli::parse_matches(matches)
+    if let Err(e) = cli::parse_matches(matches) {
+        eprintln!("error: {}", e);
+        cmd::recover(&e);
+        std::process::exit(e.exit_code())
+    }
 }
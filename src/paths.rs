@@ -0,0 +1,186 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// Expands a leading `~` and `$VAR` or `${VAR}` references in a stored path
+///
+/// Variables which are not set are left as written, and `$$` stands for a
+/// literal `$`.
+pub fn expand(path: &Path) -> PathBuf {
+    let raw = to_bytes(path.as_os_str());
+    let mut out = Vec::new();
+    let mut rest = &raw[..];
+    if let (Some(after), Some(home)) = (rest.strip_prefix(b"~"), env::var_os("HOME")) {
+        if after.is_empty() || after[0] == b'/' {
+            out.extend(to_bytes(&home));
+            rest = after;
+        }
+    }
+    while let Some(i) = rest.iter().position(|&b| b == b'$') {
+        out.extend_from_slice(&rest[..i]);
+        let after = &rest[i + 1..];
+        if after.starts_with(b"$") {
+            out.push(b'$');
+            rest = &after[1..];
+            continue;
+        }
+        let (name, len) = match after.strip_prefix(b"{") {
+            Some(braced) => match braced.iter().position(|&b| b == b'}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => (&[][..], 0),
+            },
+            None => {
+                let end = after
+                    .iter()
+                    .position(|b| !(b.is_ascii_alphanumeric() || *b == b'_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        match std::str::from_utf8(name).ok().filter(|n| !n.is_empty()).and_then(env::var_os) {
+            Some(value) => out.extend(to_bytes(&value)),
+            None => out.extend_from_slice(&rest[i..i + 1 + len]),
+        }
+        rest = &rest[i + 1 + len..];
+    }
+    out.extend_from_slice(rest);
+    PathBuf::from(from_bytes(out))
+}
+
+/// Turns a real path into its stored form, the inverse of [`expand`]
+///
+/// A path inside the home directory is rewritten to start with `~`, and
+/// every `$` is doubled so it is not taken for a variable.
+pub fn contract(path: &Path) -> PathBuf {
+    let home = env::var_os("HOME").map(PathBuf::from).filter(|h| h.parent().is_some());
+    let (prefix, rest) = match home.as_ref().and_then(|h| path.strip_prefix(h).ok()) {
+        Some(rest) => (&b"~"[..], rest),
+        None => (&b""[..], path),
+    };
+    let mut out = prefix.to_vec();
+    if !prefix.is_empty() && !rest.as_os_str().is_empty() {
+        out.push(b'/');
+    }
+    for b in to_bytes(rest.as_os_str()) {
+        if b == b'$' {
+            out.push(b'$');
+        }
+        out.push(b);
+    }
+    PathBuf::from(from_bytes(out))
+}
+
+/// Stored form of a path saved as absolute by an older version
+///
+/// `None` unless the path lies inside the home directory.
+pub fn migrate(path: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() {
+        return None;
+    }
+    Some(contract(path)).filter(|p| p.starts_with("~"))
+}
+
+#[cfg(unix)]
+pub(crate) fn to_bytes(s: &OsStr) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    s.as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+pub(crate) fn from_bytes(bytes: Vec<u8>) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(bytes)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn to_bytes(s: &OsStr) -> Vec<u8> {
+    s.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn from_bytes(bytes: Vec<u8>) -> OsString {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
+/// A path as written to JSON: a string when it is valid UTF-8, otherwise
+/// `{"bytes": [...]}` holding the raw bytes
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    Text(String),
+    Bytes { bytes: Vec<u8> },
+}
+
+impl Repr {
+    fn new(path: &Path) -> Self {
+        match path.to_str() {
+            Some(s) => Self::Text(s.to_string()),
+            None => Self::Bytes {
+                bytes: to_bytes(path.as_os_str()),
+            },
+        }
+    }
+
+    fn into_path(self) -> PathBuf {
+        match self {
+            Self::Text(s) => PathBuf::from(s),
+            Self::Bytes { bytes } => PathBuf::from(from_bytes(bytes)),
+        }
+    }
+}
+
+/// Lossless serde for paths, `#[serde(with = "crate::paths::lossless")]`
+pub(crate) mod lossless {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(path: &Path, s: S) -> Result<S::Ok, S::Error> {
+        Repr::new(path).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<PathBuf, D::Error> {
+        Repr::deserialize(d).map(Repr::into_path)
+    }
+}
+
+/// [`lossless`] for a list of paths
+pub(crate) mod lossless_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(paths: &[PathBuf], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(paths.iter().map(|p| Repr::new(p)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<PathBuf>, D::Error> {
+        let reprs = Vec::<Repr>::deserialize(d)?;
+        Ok(reprs.into_iter().map(Repr::into_path).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dollar_signs_survive_a_round_trip() {
+        env::set_var("TELE_TEST_VAR", "expanded");
+        let real = Path::new("/srv/$TELE_TEST_VAR/a$$b");
+        let stored = contract(real);
+        assert_eq!(stored, Path::new("/srv/$$TELE_TEST_VAR/a$$$$b"));
+        assert_eq!(expand(&stored), real);
+        assert_eq!(expand(Path::new("/srv/$TELE_TEST_VAR")), Path::new("/srv/expanded"));
+    }
+
+    #[test]
+    fn migrating_keeps_dollar_signs() {
+        let home = match env::var_os("HOME").map(PathBuf::from) {
+            Some(h) if h.parent().is_some() => h,
+            _ => return,
+        };
+        let legacy = home.join("a$b/$$c");
+        let stored = migrate(&legacy).unwrap();
+        assert_eq!(stored, Path::new("~/a$$b/$$$$c"));
+        assert_eq!(expand(&stored), legacy);
+        assert_eq!(migrate(Path::new("~/a")), None);
+    }
+}
@@ -0,0 +1,234 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use tele::resolve;
+use tele::{List, Waypoint};
+
+/// Returns true if the picker can be shown
+pub fn available() -> bool {
+    cfg!(unix) && atty::is(atty::Stream::Stdin) && atty::is(atty::Stream::Stderr)
+}
+
+/// Lets the user pick a waypoint by typing to filter, returning its name
+///
+/// The picker draws on `/dev/tty` as stdout is captured by the shell
+/// integration. Returns `None` when the user cancels.
+pub fn pick(list: &List) -> io::Result<Option<String>> {
+    let mut tty = Tty::open()?;
+    let mut query = String::new();
+    let mut selected = 0;
+    loop {
+        let matches = resolve::rank(list, &query);
+        selected = selected.min(matches.len().saturating_sub(1));
+        tty.draw(&query, &matches, list.0.len(), selected)?;
+        for key in tty.read_keys()? {
+            match key {
+                Key::Char(c) => {
+                    query.push(c);
+                    selected = 0
+                }
+                Key::Backspace => {
+                    query.pop();
+                    selected = 0
+                }
+                Key::Clear => {
+                    query.clear();
+                    selected = 0
+                }
+                Key::Up => selected = selected.saturating_sub(1),
+                Key::Down => selected += 1,
+                Key::Enter if matches.is_empty() => (),
+                Key::Enter => return Ok(Some(matches[selected].name.clone())),
+                Key::Cancel => return Ok(None),
+                Key::Other => (),
+            }
+        }
+    }
+}
+
+enum Key {
+    Char(char),
+    Backspace,
+    /// Ctrl-U
+    Clear,
+    Up,
+    Down,
+    Enter,
+    /// Escape, Ctrl-C or Ctrl-D
+    Cancel,
+    Other,
+}
+
+/// Splits raw terminal input into keys
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let (key, len) = match &bytes[i..] {
+            [0x1b] => (Key::Cancel, 1),
+            [0x1b, b'[', b'A', ..] | [0x1b, b'O', b'A', ..] => (Key::Up, 3),
+            [0x1b, b'[', b'B', ..] | [0x1b, b'O', b'B', ..] => (Key::Down, 3),
+            // skip any other escape sequence up to its final byte
+            [0x1b, b'[', rest @ ..] => {
+                let end = rest.iter().position(|b| (0x40..=0x7e).contains(b));
+                (Key::Other, 2 + end.map_or(rest.len(), |e| e + 1))
+            }
+            [0x1b, _, ..] => (Key::Other, 2),
+            [b'\r', ..] | [b'\n', ..] => (Key::Enter, 1),
+            [0x7f, ..] | [0x08, ..] => (Key::Backspace, 1),
+            [0x15, ..] => (Key::Clear, 1),
+            [0x10, ..] => (Key::Up, 1),
+            [0x0e, ..] => (Key::Down, 1),
+            [0x03, ..] | [0x04, ..] => (Key::Cancel, 1),
+            [b, ..] if *b < 0x20 => (Key::Other, 1),
+            [b, ..] => {
+                let len = match b {
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => 1,
+                };
+                let end = (i + len).min(bytes.len());
+                match std::str::from_utf8(&bytes[i..end]).ok().and_then(|s| s.chars().next()) {
+                    Some(c) => (Key::Char(c), len),
+                    None => (Key::Other, 1),
+                }
+            }
+            [] => break,
+        };
+        keys.push(key);
+        i += len;
+    }
+    keys
+}
+
+/// The controlling terminal, in raw mode for as long as this is alive
+struct Tty {
+    file: File,
+    mode: Mode,
+}
+
+impl Tty {
+    fn open() -> io::Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        let mode = enter_raw_mode(&file)?;
+        // switch to the alternate screen
+        file.write_all(b"\x1b[?1049h")?;
+        Ok(Self { file, mode })
+    }
+
+    fn read_keys(&mut self) -> io::Result<Vec<Key>> {
+        let mut buf = [0; 64];
+        let n = self.file.read(&mut buf)?;
+        if n == 0 {
+            return Ok(vec![Key::Cancel]);
+        }
+        Ok(parse_keys(&buf[..n]))
+    }
+
+    fn draw(&mut self, query: &str, matches: &[&Waypoint], total: usize, selected: usize) -> io::Result<()> {
+        let (rows, cols) = window_size(&self.file).unwrap_or((24, 80));
+        let visible = rows.saturating_sub(2).max(1);
+        let offset = (selected + 1).saturating_sub(visible);
+        let name_width = matches.iter().map(|w| w.name.chars().count()).max().unwrap_or(0);
+        let group_width = matches
+            .iter()
+            .map(|w| w.group.as_deref().map_or(0, |g| g.chars().count()))
+            .max()
+            .unwrap_or(0);
+
+        let mut screen = String::from("\x1b[H");
+        screen += &fit(&format!("> {}", query), cols);
+        screen += "\x1b[K\n\x1b[2m";
+        screen += &fit(&format!("  {}/{}", matches.len(), total), cols);
+        screen += "\x1b[0m\x1b[K";
+        for (i, w) in matches.iter().enumerate().skip(offset).take(visible) {
+            let line = format!(
+                "{} {:<nw$}  {:<gw$}  {}",
+                if i == selected { '>' } else { ' ' },
+                w.name,
+                w.group.as_deref().unwrap_or(""),
+                w.path.display(),
+                nw = name_width,
+                gw = group_width,
+            );
+            screen += "\n";
+            if i == selected {
+                screen += &format!("\x1b[7m{}\x1b[0m\x1b[K", fit(&line, cols))
+            } else {
+                screen += &format!("{}\x1b[K", fit(&line, cols))
+            }
+        }
+        // clear below and put the cursor after the query
+        screen += &format!("\x1b[J\x1b[1;{}H", (query.chars().count() + 3).min(cols));
+        self.file.write_all(screen.as_bytes())?;
+        self.file.flush()
+    }
+}
+
+impl Drop for Tty {
+    fn drop(&mut self) {
+        self.file.write_all(b"\x1b[?1049l").ok();
+        restore_mode(&self.file, &self.mode)
+    }
+}
+
+/// Truncates `s` to `width` characters
+fn fit(s: &str, width: usize) -> String {
+    s.chars().take(width).collect()
+}
+
+#[cfg(unix)]
+type Mode = libc::termios;
+
+#[cfg(not(unix))]
+type Mode = ();
+
+/// Disables line buffering, echo and signal keys, returning the previous mode
+#[cfg(unix)]
+fn enter_raw_mode(file: &File) -> io::Result<Mode> {
+    use std::os::unix::io::AsRawFd;
+    let fd = file.as_raw_fd();
+    let mut original: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut raw = original;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+    raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+    raw.c_cc[libc::VMIN] = 1;
+    raw.c_cc[libc::VTIME] = 0;
+    if unsafe { libc::tcsetattr(fd, libc::TCSAFLUSH, &raw) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(original)
+}
+
+#[cfg(not(unix))]
+fn enter_raw_mode(_file: &File) -> io::Result<Mode> {
+    Err(io::Error::new(io::ErrorKind::Other, "the picker needs a unix terminal"))
+}
+
+#[cfg(unix)]
+fn restore_mode(file: &File, mode: &Mode) {
+    use std::os::unix::io::AsRawFd;
+    unsafe { libc::tcsetattr(file.as_raw_fd(), libc::TCSAFLUSH, mode) };
+}
+
+#[cfg(not(unix))]
+fn restore_mode(_file: &File, _mode: &Mode) {}
+
+/// Rows and columns of the terminal
+#[cfg(unix)]
+fn window_size(file: &File) -> Option<(usize, usize)> {
+    use std::os::unix::io::AsRawFd;
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    match unsafe { libc::ioctl(file.as_raw_fd(), libc::TIOCGWINSZ, &mut size) } {
+        0 if size.ws_row > 0 && size.ws_col > 0 => Some((size.ws_row as usize, size.ws_col as usize)),
+        _ => None,
+    }
+}
+
+#[cfg(not(unix))]
+fn window_size(_file: &File) -> Option<(usize, usize)> {
+    None
+}
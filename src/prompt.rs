@@ -0,0 +1,28 @@
+use std::io::{self, BufRead, Write};
+
+/// Returns true if questions can be answered interactively
+pub fn interactive() -> bool {
+    atty::is(atty::Stream::Stdin)
+}
+
+/// Asks a question on stderr and returns the trimmed answer
+///
+/// Returns `None` when stdin is not a terminal, so scripts never block.
+pub fn ask(question: &str) -> Option<String> {
+    if !interactive() {
+        return None;
+    }
+    eprint!("{} ", question);
+    io::stderr().flush().ok();
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer).ok()?;
+    Some(answer.trim().to_string())
+}
+
+/// Asks a yes/no question, defaulting to no
+pub fn confirm(question: &str) -> bool {
+    matches!(
+        ask(&format!("{} [y/N]", question)).as_deref(),
+        Some("y") | Some("Y") | Some("yes")
+    )
+}
@@ -0,0 +1,222 @@
+use crate::waypoints::{List, Waypoint};
+use std::cmp::Ordering;
+
+/// Minimum lead the best fuzzy match needs over the runner-up to be picked
+const CLEAR_MARGIN: i64 = 16;
+/// Maximum number of candidates reported for an ambiguous query
+const MAX_CANDIDATES: usize = 8;
+
+/// Outcome of resolving a query against the waypoint list
+pub enum Resolution<'a> {
+    /// A single waypoint clearly matches
+    Found(&'a Waypoint),
+    /// Several waypoints match, ranked best first
+    Ambiguous(Vec<&'a Waypoint>),
+    /// Nothing matches
+    NotFound,
+}
+
+/// Resolves `query` by trying an exact name, then a unique name prefix,
+/// then fuzzy subsequence scoring over names, groups and path components
+pub fn resolve<'a>(list: &'a List, query: &str) -> Resolution<'a> {
+    if let Some(w) = list.get_entry(query) {
+        return Resolution::Found(w);
+    }
+
+    let q = query.to_lowercase();
+    let mut prefixed: Vec<&Waypoint> = list
+        .0
+        .iter()
+        .filter(|w| w.name.to_lowercase().starts_with(&q))
+        .collect();
+    match prefixed.len() {
+        0 => (),
+        1 => return Resolution::Found(prefixed[0]),
+        _ => {
+            prefixed.sort_by(|a, b| {
+                by_frecency(a, b)
+                    .then(a.name.len().cmp(&b.name.len()))
+                    .then(a.name.cmp(&b.name))
+            });
+            prefixed.truncate(MAX_CANDIDATES);
+            return Resolution::Ambiguous(prefixed);
+        }
+    }
+
+    let scored = score_all(list, &q);
+    match scored.as_slice() {
+        [] => Resolution::NotFound,
+        [(_, w)] => Resolution::Found(w),
+        [(best, w), (second, _), ..] if best - second >= CLEAR_MARGIN => Resolution::Found(w),
+        _ => Resolution::Ambiguous(
+            scored
+                .into_iter()
+                .take(MAX_CANDIDATES)
+                .map(|(_, w)| w)
+                .collect(),
+        ),
+    }
+}
+
+/// Every waypoint matching `query`, best first
+///
+/// Waypoints are scored like the fuzzy stage of [`resolve`]. An empty query
+/// matches everything, ordered by frecency.
+pub fn rank<'a>(list: &'a List, query: &str) -> Vec<&'a Waypoint> {
+    score_all(list, &query.to_lowercase())
+        .into_iter()
+        .map(|(_, w)| w)
+        .collect()
+}
+
+/// Scores every waypoint matching the lowercased query `q`, best first
+fn score_all<'a>(list: &'a List, q: &str) -> Vec<(i64, &'a Waypoint)> {
+    let mut scored: Vec<(i64, &Waypoint)> = list
+        .0
+        .iter()
+        .filter_map(|w| {
+            if q.is_empty() {
+                Some((0, w))
+            } else {
+                score_waypoint(q, w).map(|s| (s, w))
+            }
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then(by_frecency(a.1, b.1))
+            .then(a.1.name.cmp(&b.1.name))
+    });
+    scored
+}
+
+/// Orders more frequently and recently visited waypoints first
+fn by_frecency(a: &Waypoint, b: &Waypoint) -> Ordering {
+    b.frecency().partial_cmp(&a.frecency()).unwrap_or(Ordering::Equal)
+}
+
+/// Scores a waypoint, favouring its name over its group and path
+fn score_waypoint(query: &str, w: &Waypoint) -> Option<i64> {
+    let name = fuzzy_score(query, &w.name);
+    let group = w.group.as_ref().and_then(|g| fuzzy_score(query, g));
+    let path = w
+        .path
+        .iter()
+        .filter_map(|c| fuzzy_score(query, &c.to_string_lossy()))
+        .max();
+    let secondary = group.max(path).map(|s| s / 2);
+    name.max(secondary)
+}
+
+/// Scores `target` as a case-insensitive subsequence match of `query`
+///
+/// Consecutive characters and characters following a word boundary score
+/// higher, while gaps between matched characters are penalised.
+/// Returns `None` when `query` is not a subsequence of `target`.
+fn fuzzy_score(query: &str, target: &str) -> Option<i64> {
+    let q: Vec<char> = query.chars().collect();
+    let t: Vec<char> = target.to_lowercase().chars().collect();
+    if q.is_empty() {
+        return None;
+    }
+    (0..t.len())
+        .filter(|&start| t[start] == q[0])
+        .filter_map(|start| score_from(&q, &t, start))
+        .max()
+}
+
+fn score_from(q: &[char], t: &[char], start: usize) -> Option<i64> {
+    let mut score = -(start.min(8) as i64);
+    let mut prev: Option<usize> = None;
+    let mut ti = start;
+    for &c in q {
+        while ti < t.len() && t[ti] != c {
+            ti += 1;
+        }
+        if ti == t.len() {
+            return None;
+        }
+        score += 16;
+        match prev {
+            Some(p) if p + 1 == ti => score += 16,
+            Some(p) => score -= (ti - p - 1) as i64,
+            None => (),
+        }
+        if ti == 0 || is_boundary(t[ti - 1]) {
+            score += 24;
+        }
+        prev = Some(ti);
+        ti += 1;
+    }
+    if q.len() == t.len() {
+        score += 8;
+    }
+    Some(score)
+}
+
+fn is_boundary(c: char) -> bool {
+    matches!(c, '-' | '_' | '.' | '/' | ' ')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn list(names: &[&str]) -> List {
+        List(names.iter().map(|n| Waypoint::new(n, Path::new("/tmp"), None)).collect())
+    }
+
+    fn names(ws: &[&Waypoint]) -> Vec<String> {
+        ws.iter().map(|w| w.name.clone()).collect()
+    }
+
+    #[test]
+    fn exact_name_wins_over_prefix() {
+        let list = list(&["proj", "project-api"]);
+        assert!(matches!(resolve(&list, "proj"), Resolution::Found(w) if w.name == "proj"));
+    }
+
+    #[test]
+    fn unique_prefix() {
+        let list = list(&["project-api", "notes"]);
+        assert!(matches!(resolve(&list, "proj"), Resolution::Found(w) if w.name == "project-api"));
+    }
+
+    #[test]
+    fn fuzzy_clear_winner() {
+        let list = list(&["project-api", "pineapple", "notes"]);
+        assert!(matches!(resolve(&list, "pa"), Resolution::Found(w) if w.name == "project-api"));
+    }
+
+    #[test]
+    fn fuzzy_ambiguous_in_rank_order() {
+        let list = list(&["xy-a", "x-a", "notes"]);
+        match resolve(&list, "xa") {
+            Resolution::Ambiguous(ws) => assert_eq!(names(&ws), ["x-a", "xy-a"]),
+            _ => panic!("expected an ambiguous match"),
+        }
+    }
+
+    #[test]
+    fn prefix_ties_broken_by_frecency() {
+        let mut list = list(&["proj-a", "proj-b"]);
+        list.0[1] = list.0[1].visit().visit();
+        match resolve(&list, "proj") {
+            Resolution::Ambiguous(ws) => assert_eq!(names(&ws), ["proj-b", "proj-a"]),
+            _ => panic!("expected an ambiguous match"),
+        }
+    }
+
+    #[test]
+    fn name_scores_above_group_and_path() {
+        let mut list = list(&["api"]);
+        list.0.push(Waypoint::new("other", Path::new("/src/api"), Some("api")));
+        assert_eq!(names(&rank(&list, "api")), ["api", "other"]);
+    }
+
+    #[test]
+    fn no_match() {
+        assert!(matches!(resolve(&list(&["notes"]), "zz"), Resolution::NotFound));
+    }
+}
@@ -0,0 +1,307 @@
+use crate::error::{Error, Result};
+use crate::paths;
+use crate::waypoints::{List, Waypoint};
+use std::path::{Path, PathBuf};
+
+/// Formats of `tele export`
+#[derive(Clone, Copy, Debug)]
+pub enum Format {
+    Json,
+    Toml,
+    /// A standalone script defining an alias per waypoint
+    Sh,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "toml" => Self::Toml,
+            "sh" => Self::Sh,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// What `tele import` does with a waypoint whose name is already taken
+#[derive(Clone, Copy, Debug)]
+pub enum Conflict {
+    Skip,
+    /// Import it under a free name
+    Rename,
+    /// Replace the existing waypoint's path and group
+    Overwrite,
+}
+
+impl Conflict {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "rename" => Self::Rename,
+            "overwrite" => Self::Overwrite,
+            _ => Self::Skip,
+        }
+    }
+}
+
+/// Renders `list` for sharing, leaving out visit statistics
+pub fn export(list: &List, format: Format) -> String {
+    let shared = List(
+        list.0
+            .iter()
+            .map(|w| Waypoint::new(&w.name, &w.path, w.group.as_deref()))
+            .collect(),
+    );
+    match format {
+        Format::Json => serde_json::to_string_pretty(&shared).expect("could not serialize list") + "\n",
+        Format::Toml => to_toml(&shared),
+        Format::Sh => to_sh(&shared),
+    }
+}
+
+/// Reads a list written by [`export`] as JSON or TOML
+pub fn parse(path: &Path, data: &[u8]) -> Result<List> {
+    let text = String::from_utf8_lossy(data);
+    let start = text.trim_start();
+    if start.starts_with('[') && !start.starts_with("[[") {
+        return serde_json::from_str(&text).map_err(|source| Error::Parse {
+            path: path.to_path_buf(),
+            source,
+        });
+    }
+    from_toml(&text).map_err(|(line, message)| Error::Syntax {
+        path: path.to_path_buf(),
+        line,
+        message,
+    })
+}
+
+fn to_toml(list: &List) -> String {
+    let mut out = String::new();
+    for w in &list.0 {
+        out += "[[waypoint]]\n";
+        out += &format!("name = {}\n", toml_string(&w.name));
+        match w.path.to_str() {
+            Some(p) => out += &format!("path = {}\n", toml_string(p)),
+            None => out += &format!("path = {{ bytes = {:?} }}\n", paths::to_bytes(w.path.as_os_str())),
+        }
+        if let Some(g) = &w.group {
+            out += &format!("group = {}\n", toml_string(g));
+        }
+        out += "\n";
+    }
+    out
+}
+
+fn toml_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '\t' => out += "\\t",
+            '\r' => out += "\\r",
+            c if c.is_control() => out += &format!("\\u{:04X}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out + "\""
+}
+
+/// Parses the subset of TOML written by [`export`]: `[[waypoint]]` tables
+/// holding strings, with paths optionally given as `{ bytes = [...] }`
+fn from_toml(text: &str) -> std::result::Result<List, (usize, String)> {
+    let mut list = List::default();
+    let mut starts = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let err = |message: &str| (i + 1, message.to_string());
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line == "[[waypoint]]" {
+            list.0.push(Waypoint::new("", Path::new(""), None));
+            starts.push(i + 1);
+            continue;
+        }
+        let w = list.0.last_mut().ok_or_else(|| err("expected [[waypoint]]"))?;
+        let (key, value) = line.split_once('=').ok_or_else(|| err("expected key = value"))?;
+        match (key.trim(), value.trim()) {
+            ("path", v) if v.starts_with('{') => {
+                w.path = parse_bytes(v).ok_or_else(|| err("expected { bytes = [...] }"))?
+            }
+            (key, v) => {
+                let s = parse_string(v).ok_or_else(|| err("expected a string"))?;
+                match key {
+                    "name" => w.name = s,
+                    "path" => w.path = PathBuf::from(s),
+                    "group" => w.group = Some(s),
+                    _ => (),
+                }
+            }
+        }
+    }
+    match list.0.iter().position(|w| w.name.is_empty() || w.path.as_os_str().is_empty()) {
+        Some(i) => Err((starts[i], "waypoints need a name and a path".to_string())),
+        None => Ok(list),
+    }
+}
+
+/// A basic `"..."` or literal `'...'` string, followed by an optional comment
+fn parse_string(v: &str) -> Option<String> {
+    if let Some(rest) = v.strip_prefix('\'') {
+        let end = rest.find('\'')?;
+        return trailing_ok(&rest[end + 1..]).then(|| rest[..end].to_string());
+    }
+    let mut chars = v.strip_prefix('"')?.chars();
+    let mut out = String::new();
+    loop {
+        match chars.next()? {
+            '"' => break,
+            '\\' => match chars.next()? {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                'r' => out.push('\r'),
+                '"' => out.push('"'),
+                '\\' => out.push('\\'),
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    out.push(std::char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?)
+                }
+                _ => return None,
+            },
+            c => out.push(c),
+        }
+    }
+    if trailing_ok(chars.as_str()) {
+        Some(out)
+    } else {
+        None
+    }
+}
+
+fn trailing_ok(rest: &str) -> bool {
+    let rest = rest.trim();
+    rest.is_empty() || rest.starts_with('#')
+}
+
+/// `{ bytes = [1, 2, ...] }`
+fn parse_bytes(v: &str) -> Option<PathBuf> {
+    let inner = v.strip_prefix('{')?.split('}').next()?;
+    let (key, list) = inner.split_once('=')?;
+    if key.trim() != "bytes" {
+        return None;
+    }
+    let list = list.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let bytes = list
+        .split(',')
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .map(|b| b.parse().ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some(PathBuf::from(paths::from_bytes(bytes)))
+}
+
+fn to_sh(list: &List) -> String {
+    let mut out = String::from(
+        "#!/bin/sh\n\
+         # waypoints exported by tele, source this file to define an alias per waypoint\n",
+    );
+    let mut group = None;
+    for w in &list.0 {
+        if w.group != group {
+            group = w.group.clone();
+            out += &format!("\n# {}\n", group.as_deref().unwrap_or("ungrouped"));
+        }
+        match w.path.to_str() {
+            Some(path) if valid_alias(&w.name) => {
+                out += &format!("alias {}={}\n", w.name, sh_quote(&format!("cd -- {}", sh_path(path))))
+            }
+            Some(_) => out += &format!("# skipped '{}': not a valid alias name\n", w.name),
+            None => out += &format!("# skipped '{}': path is not valid UTF-8\n", w.name),
+        }
+    }
+    out
+}
+
+fn valid_alias(name: &str) -> bool {
+    !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// Double quotes a stored path, leaving `~` and `$VAR` for the shell to expand
+fn sh_path(path: &str) -> String {
+    let (home, rest) = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => ("$HOME", rest),
+        _ => ("", path),
+    };
+    // `$$` is a literal `$` in stored paths but the PID to the shell
+    let escaped = rest
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('`', "\\`")
+        .replace("$$", "\\$");
+    format!("\"{}{}\"", home, escaped)
+}
+
+fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> List {
+        let mut odd = b"/src/bad".to_vec();
+        odd.push(0xff);
+        List(vec![
+            Waypoint::new("plain", Path::new("~/src/plain"), None),
+            Waypoint::new("quotes", Path::new("/src/\"it's\" \\ here"), Some("work/client \"A\"")),
+            Waypoint::new("control", Path::new("/src/tab\there\nnew\u{1}line"), Some("g")),
+            Waypoint::new("bytes", &PathBuf::from(paths::from_bytes(odd)), Some("work")),
+        ])
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let list = sample();
+        let text = export(&list, Format::Toml);
+        assert_eq!(parse(Path::new("x.toml"), text.as_bytes()).unwrap(), list);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let list = sample();
+        let text = export(&list, Format::Json);
+        assert_eq!(parse(Path::new("x.json"), text.as_bytes()).unwrap(), list);
+    }
+
+    #[test]
+    fn export_leaves_out_visits() {
+        let list = List(vec![Waypoint::new("a", Path::new("/a"), None).visit()]);
+        let parsed = parse(Path::new("x.toml"), export(&list, Format::Toml).as_bytes()).unwrap();
+        assert_eq!(parsed.0[0].visits, 0);
+        assert_eq!(parsed.0[0].last_visit, None);
+    }
+
+    #[test]
+    fn toml_literal_strings_and_comments() {
+        let text = "# shared waypoints\n[[waypoint]]\nname = 'a' # first\npath = '/src/a\\b'\n";
+        let list = parse(Path::new("x.toml"), text.as_bytes()).unwrap();
+        assert_eq!(list, List(vec![Waypoint::new("a", Path::new("/src/a\\b"), None)]));
+    }
+
+    #[test]
+    fn toml_errors_report_the_line() {
+        let line = |text: &str| match parse(Path::new("x.toml"), text.as_bytes()) {
+            Err(Error::Syntax { line, .. }) => line,
+            _ => panic!("expected a syntax error"),
+        };
+        assert_eq!(line("name = \"a\"\n"), 1);
+        assert_eq!(line("[[waypoint]]\nname = \"a\"\npath = \"unterminated\n"), 3);
+        assert_eq!(line("[[waypoint]]\nname = \"a\"\n\n[[waypoint]]\npath = { bytes = [1, 300] }\n"), 5);
+        assert_eq!(line("[[waypoint]]\nname = \"a\"\n"), 1);
+    }
+}
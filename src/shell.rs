@@ -0,0 +1,147 @@
+/// Shells supported by `tele init`
+pub enum Shell {
+    Sh,
+    Bash,
+    Zsh,
+    Fish,
+    Nushell,
+}
+
+impl Shell {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "sh" => Some(Self::Sh),
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            "fish" => Some(Self::Fish),
+            "nushell" | "nu" => Some(Self::Nushell),
+            _ => None,
+        }
+    }
+}
+
+/// Returns true if `name` can safely be used as a function name in every shell
+///
+/// Only POSIX names (`[A-Za-z_][A-Za-z0-9_]*`) qualify, as dash rejects
+/// anything else.
+pub fn valid_function_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Shell integration defining the `cmd` function
+///
+/// The function runs `tele`, `cd`s into its output when it exits with
+/// status 2 and otherwise echoes the output and forwards the status.
+/// As the output is captured, the function tells `tele` whether its own
+/// stdout is a terminal through `TELE_STDOUT_TTY`.
+/// Completions for both `cmd` and `tele` are registered where supported.
+pub fn init_script(shell: Shell, cmd: &str) -> String {
+    let script = match shell {
+        Shell::Sh => POSIX.to_string(),
+        Shell::Bash => format!("{}{}", BASH, BASH_COMPLETION),
+        Shell::Zsh => format!("{}{}", BASH, ZSH_COMPLETION),
+        Shell::Fish => format!("{}{}", FISH, FISH_COMPLETION),
+        Shell::Nushell => NUSHELL.to_string(),
+    };
+    script.replace("{cmd}", cmd)
+}
+
+const POSIX: &str = r#"# tele shell integration (POSIX sh)
+# eval "$(tele init sh)"
+{cmd}() {
+  if [ -t 1 ]; then __tele_tty=1; else __tele_tty=0; fi
+  __tele_output="$(TELE_STDOUT_TTY=$__tele_tty command tele "$@")"
+  __tele_status=$?
+  if [ "$__tele_status" -eq 2 ]; then
+    cd -- "$__tele_output" || return
+  else
+    [ -n "$__tele_output" ] && printf '%s\n' "$__tele_output"
+    return "$__tele_status"
+  fi
+}
+"#;
+
+const BASH: &str = r#"# tele shell integration (bash/zsh)
+# eval "$(tele init bash)" or eval "$(tele init zsh)"
+{cmd}() {
+  local __tele_output __tele_status __tele_tty
+  if [ -t 1 ]; then __tele_tty=1; else __tele_tty=0; fi
+  __tele_output="$(TELE_STDOUT_TTY=$__tele_tty command tele "$@")"
+  __tele_status=$?
+  if [ "$__tele_status" -eq 2 ]; then
+    cd -- "$__tele_output" || return
+  else
+    [ -n "$__tele_output" ] && printf '%s\n' "$__tele_output"
+    return "$__tele_status"
+  fi
+}
+"#;
+
+const BASH_COMPLETION: &str = r#"
+_tele_complete() {
+  local IFS=$'\n'
+  COMPREPLY=($(command tele complete -- "${COMP_WORDS[@]:1:COMP_CWORD}"))
+  if [ "${#COMPREPLY[@]}" -eq 1 ] && [ "${COMPREPLY[0]%/}" != "${COMPREPLY[0]}" ]; then
+    compopt -o nospace
+  fi
+}
+complete -F _tele_complete {cmd} tele
+"#;
+
+const ZSH_COMPLETION: &str = r#"
+_tele_complete() {
+  local -a candidates
+  candidates=(${(f)"$(command tele complete -- "${(@)words[2,CURRENT]}")"})
+  compadd -S '' -- ${(M)candidates:#*/}
+  compadd -- ${candidates:#*/}
+}
+if (( $+functions[compdef] )); then
+  compdef _tele_complete {cmd} tele
+fi
+"#;
+
+const FISH: &str = r#"# tele shell integration (fish)
+# tele init fish | source
+function {cmd} --wraps tele --description 'Teleport to a waypoint'
+    set -lx TELE_STDOUT_TTY 0
+    isatty stdout; and set TELE_STDOUT_TTY 1
+    set -l tele_output (command tele $argv | string collect)
+    set -l tele_status $pipestatus[1]
+    if test $tele_status -eq 2
+        cd -- $tele_output
+    else
+        test -n "$tele_output"; and printf '%s\n' $tele_output
+        return $tele_status
+    end
+end
+"#;
+
+const FISH_COMPLETION: &str = r#"
+function __tele_complete
+    set -l tokens (commandline -opc)
+    set -e tokens[1]
+    command tele complete -- $tokens (commandline -ct)
+end
+complete -c {cmd} -f -a '(__tele_complete)'
+complete -c tele -f -a '(__tele_complete)'
+"#;
+
+const NUSHELL: &str = r#"# tele shell integration (nushell)
+# tele init nushell | save -f ~/.cache/tele.nu
+# source ~/.cache/tele.nu
+def --env --wrapped {cmd} [...args: string] {
+    let tty = if (is-terminal --stdout) { "1" } else { "0" }
+    let result = (with-env { TELE_STDOUT_TTY: $tty } { ^tele ...$args | complete })
+    if $result.exit_code == 2 {
+        cd ($result.stdout | str trim --right --char "\n")
+    } else {
+        print --no-newline $result.stdout
+        print --no-newline --stderr $result.stderr
+        if $result.exit_code != 0 {
+            error make --unspanned { msg: $"tele exited with status ($result.exit_code)" }
+        }
+    }
+}
+"#;
@@ -0,0 +1,242 @@
+use crate::error::{Error, Result};
+use crate::waypoints::{List, Waypoint};
+use crate::TELE;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Persistence for a waypoint [`List`]
+pub trait Store {
+    /// Reads the list, returning an empty one if nothing has been saved yet
+    fn load(&self) -> Result<List>;
+    /// Replaces the stored list
+    fn save(&self, list: &List) -> Result<()>;
+}
+
+/// JSON file backed store
+///
+/// Saving writes to a temporary file which is then renamed over the store,
+/// keeping the previous version as `<file>.bak`. Concurrent writers should
+/// hold [`FileStore::lock`] across their load-modify-save cycle.
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Store used by the `tele` binary
+    /// ~/.config/tele/waypoints.json
+    pub fn open_default() -> Self {
+        Self::new(TELE.config_dir().join("waypoints.json"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Copy of the store as it was before the last save
+    pub fn backup_path(&self) -> PathBuf {
+        self.sibling(".bak")
+    }
+
+    /// Where [`FileStore::replace_damaged`] keeps the damaged file
+    pub fn damaged_path(&self) -> PathBuf {
+        self.sibling(".broken")
+    }
+
+    /// Reads the list as it was before the last save
+    pub fn load_backup(&self) -> Result<List> {
+        read_list(&self.backup_path())
+    }
+
+    /// Raw contents of the store
+    pub fn read_to_string(&self) -> Result<String> {
+        fs::read_to_string(&self.path).map_err(|e| Error::io(&self.path, e))
+    }
+
+    /// Replaces a damaged store with `list`
+    ///
+    /// The damaged file is moved to [`FileStore::damaged_path`] and the
+    /// backup is left untouched.
+    pub fn replace_damaged(&self, list: &List) -> Result<PathBuf> {
+        let damaged = self.damaged_path();
+        if self.path.exists() {
+            fs::copy(&self.path, &damaged).map_err(|e| Error::io(&damaged, e))?;
+        }
+        self.write(list)?;
+        Ok(damaged)
+    }
+
+    /// Takes an exclusive advisory lock on the store, blocking until it is
+    /// available. The lock is released when the returned guard is dropped.
+    pub fn lock(&self) -> Result<StoreLock> {
+        let path = self.sibling(".lock");
+        self.create_dir()?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|e| Error::io(&path, e))?;
+        lock_exclusive(&file).map_err(|e| Error::io(&path, e))?;
+        Ok(StoreLock { _file: file })
+    }
+
+    /// `path` with `suffix` appended to its file name
+    fn sibling(&self, suffix: &str) -> PathBuf {
+        let mut s: OsString = self.path.clone().into_os_string();
+        s.push(suffix);
+        PathBuf::from(s)
+    }
+
+    fn create_dir(&self) -> Result<()> {
+        create_parent(&self.path)
+    }
+
+    /// Writes `list` to a temporary file and renames it over the store
+    fn write(&self, list: &List) -> Result<()> {
+        let json = serde_json::to_string_pretty(list).expect("could not serialize input");
+        write_atomic(&self.path, json.as_bytes())
+    }
+}
+
+impl Store for FileStore {
+    fn load(&self) -> Result<List> {
+        if fs::metadata(&self.path).is_err() {
+            return Ok(List::default());
+        }
+        read_list(&self.path)
+    }
+
+    fn save(&self, list: &List) -> Result<()> {
+        if self.path.exists() {
+            let bak = self.backup_path();
+            fs::copy(&self.path, &bak).map_err(|e| Error::io(&bak, e))?;
+        }
+        self.write(list)
+    }
+}
+
+fn read_list(path: &Path) -> Result<List> {
+    let file_string = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+    serde_json::from_str(&file_string).map_err(|source| Error::Parse {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Recovers every well-formed waypoint from a damaged store
+///
+/// Each `{` starts an attempt to read a single waypoint; text that cannot be
+/// read is skipped. Later duplicates of a name are dropped.
+pub fn salvage(text: &str) -> List {
+    let mut list = List::default();
+    let mut i = 0;
+    while let Some(offset) = text[i..].find('{') {
+        let start = i + offset;
+        let mut stream = serde_json::Deserializer::from_str(&text[start..]).into_iter::<Waypoint>();
+        match stream.next() {
+            Some(Ok(w)) => {
+                if list.get_entry(&w.name).is_none() {
+                    list.0.push(w)
+                }
+                i = start + stream.byte_offset();
+            }
+            _ => i = start + 1,
+        }
+    }
+    list
+}
+
+/// Guard returned by [`FileStore::lock`]
+pub struct StoreLock {
+    _file: File,
+}
+
+fn create_parent(path: &Path) -> Result<()> {
+    match path.parent() {
+        Some(dir) => fs::create_dir_all(dir).map_err(|e| Error::io(dir, e)),
+        None => Ok(()),
+    }
+}
+
+/// Writes `contents` to a temporary file and renames it over `path`
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    create_parent(path)?;
+    let mut tmp = path.as_os_str().to_os_string();
+    tmp.push(format!(".{}.tmp", process::id()));
+    let tmp = PathBuf::from(tmp);
+    write_synced(&tmp, contents).map_err(|e| Error::io(&tmp, e))?;
+    fs::rename(&tmp, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        Error::io(path, e)
+    })
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+#[cfg(unix)]
+fn lock_exclusive(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    loop {
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn lock_exclusive(_file: &File) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &List) -> Vec<&str> {
+        list.0.iter().map(|w| w.name.as_str()).collect()
+    }
+
+    #[test]
+    fn salvage_skips_a_missing_comma() {
+        let text = r#"[
+  {"name": "a", "path": "/a", "group": null}
+  {"name": "b", "path": "/b", "group": "g"},
+  {"name": "c", "path": "/c", "group": null}
+]"#;
+        assert!(serde_json::from_str::<List>(text).is_err());
+        let list = salvage(text);
+        assert_eq!(names(&list), ["a", "b", "c"]);
+        assert_eq!(list.0[1].group.as_deref(), Some("g"));
+    }
+
+    #[test]
+    fn salvage_drops_a_truncated_entry() {
+        let text = r#"[{"name": "a", "path": "/a", "group": null}, {"name": "b", "pa"#;
+        assert_eq!(names(&salvage(text)), ["a"]);
+    }
+
+    #[test]
+    fn salvage_keeps_the_first_of_duplicate_names() {
+        let text = r#"[{"name": "a", "path": "/first", "group": null},
+            {"name": "a", "path": "/second", "group": null},
+            {"name": 3}, {"name": "b", "path": {"bytes": [47, 255]}, "group": null}]"#;
+        let list = salvage(text);
+        assert_eq!(names(&list), ["a", "b"]);
+        assert_eq!(list.0[0].path, Path::new("/first"));
+    }
+}
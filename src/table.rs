@@ -1,70 +1,230 @@
-use crate::waypoints::List;
+use crate::term;
+use std::fs;
+use tele::{List, PathStatus, Waypoint};
 use cli_table::format::*;
 use cli_table::{Cell, Row, Table};
 
+/// Cell styles, rendered as `cli_table` formats when color is enabled
+#[derive(Clone, Copy)]
+enum Style {
+    Plain,
+    Head,
+    Group,
+    Warning,
+    Error,
+}
+
+type Rows = Vec<Vec<(String, Style)>>;
+
 macro_rules! row {
-    ($($name:expr, $fmt:expr;)*) => {
-        Row::new(vec![
-            $(Cell::new($name, $fmt),)*
-        ])
+    ($($name:expr, $style:expr;)*) => {
+        vec![
+            $(($name.to_string(), $style),)*
+        ]
     }
 }
 
+/// Prints waypoints as a tree of their groups
 pub fn print_all(list: List) {
     let mut rows = vec![row! {
-        "name", head_format();
-        "group", head_format();
-        "path", head_format();
+        "name", Style::Head;
+        "path", Style::Head;
+    }];
+    Tree::new(&list, None).rows(&mut rows, "", true);
+    print(rows)
+}
+
+/// Prints waypoints in the given order, e.g. ranked candidates for a query
+pub fn print_flat(list: List) {
+    let mut rows = vec![row! {
+        "name", Style::Head;
+        "group", Style::Head;
+        "path", Style::Head;
     }];
     for w in &list.0 {
-        let g = match &w.group {
-            Some(g) => g,
-            None => "",
-        };
+        let (path, path_style) = path_cell(w);
         rows.push(row! {
-            &w.name, Default::default();
-            &g, Default::default();
-            &w.path, Default::default();
+            &w.name, Style::Plain;
+            w.group.as_deref().unwrap_or_default(), Style::Plain;
+            &path, path_style;
         })
     }
-    Table::new(rows, table_format())
-        .print_stdout()
-        .expect("error printing table")
+    print(rows)
 }
 
+/// Prints the waypoints of `group`, with its subgroups as a tree
 pub fn print_group(list: List, group: &str) {
     let mut rows = vec![row! {
-        "name", head_format();
-        group, head_format();
+        "name", Style::Head;
+        group, Style::Head;
     }];
-    for w in &list.0 {
-        rows.push(row! {
-            &w.name, Default::default();
-            &w.path, Default::default();
-        })
-    }
-    Table::new(rows, table_format())
-        .print_stdout()
-        .expect("error printing table")
+    Tree::new(&list, Some(group)).rows(&mut rows, "", true);
+    print(rows)
 }
 
 pub fn print_groupless(list: List) {
     let mut rows = vec![row! {
-        "name", head_format();
-        "path", head_format();
+        "name", Style::Head;
+        "path", Style::Head;
     }];
     for w in &list.0 {
+        let (path, path_style) = path_cell(w);
         let mut r = vec![row! {
-            &w.name, Default::default();
-            &w.path, Default::default();
+            &w.name, Style::Plain;
+            &path, path_style;
         }];
         rows.append(&mut r)
     }
+    print(rows)
+}
+
+/// Waypoints arranged by their group paths
+#[derive(Default)]
+struct Tree<'a> {
+    waypoints: Vec<&'a Waypoint>,
+    groups: Vec<(&'a str, Tree<'a>)>,
+}
+
+impl<'a> Tree<'a> {
+    /// Builds the tree of `list`, with groups relative to `root`
+    fn new(list: &'a List, root: Option<&str>) -> Self {
+        let mut tree = Self::default();
+        for w in &list.0 {
+            let group = w.group.as_deref().unwrap_or_default();
+            let relative = match root.map(|r| r.trim_matches('/')) {
+                // only whole segments, `work-b` is not inside `work`
+                Some(r) => match group.strip_prefix(r) {
+                    Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+                    _ => group,
+                },
+                None => group,
+            };
+            let mut node = &mut tree;
+            for segment in relative.split('/').filter(|s| !s.is_empty()) {
+                let i = match node.groups.iter().position(|(g, _)| *g == segment) {
+                    Some(i) => i,
+                    None => {
+                        node.groups.push((segment, Self::default()));
+                        node.groups.len() - 1
+                    }
+                };
+                node = &mut node.groups[i].1;
+            }
+            node.waypoints.push(w);
+        }
+        tree
+    }
+
+    /// Appends a row per waypoint and subgroup, drawing branches after `indent`
+    fn rows(&self, rows: &mut Rows, indent: &str, top: bool) {
+        let count = self.waypoints.len() + self.groups.len();
+        let branch = |i: usize| match (top, i + 1 == count) {
+            (true, _) => "",
+            (false, true) => "└─ ",
+            (false, false) => "├─ ",
+        };
+        for (i, w) in self.waypoints.iter().enumerate() {
+            let (path, path_style) = path_cell(w);
+            rows.push(row! {
+                format!("{}{}{}", indent, branch(i), w.name), Style::Plain;
+                &path, path_style;
+            })
+        }
+        for (i, (group, tree)) in self.groups.iter().enumerate() {
+            let i = self.waypoints.len() + i;
+            rows.push(row! {
+                format!("{}{}{}/", indent, branch(i), group), Style::Group;
+                "", Style::Plain;
+            });
+            let indent = match (top, i + 1 == count) {
+                (true, _) => indent.to_string(),
+                (false, true) => format!("{}   ", indent),
+                (false, false) => format!("{}│  ", indent),
+            };
+            tree.rows(rows, &indent, false)
+        }
+    }
+}
+
+/// Path annotated with its status, highlighted when teleporting would fail
+fn path_cell(w: &Waypoint) -> (String, Style) {
+    let path = w.path.display();
+    match w.status() {
+        PathStatus::Ok => (path.to_string(), Style::Plain),
+        PathStatus::Symlink => match fs::canonicalize(w.expanded_path()) {
+            Ok(target) => (format!("{} -> {}", path, target.display()), Style::Plain),
+            Err(_) => (path.to_string(), Style::Plain),
+        },
+        PathStatus::Unreadable => (
+            format!("{} ({})", path, PathStatus::Unreadable.describe()),
+            Style::Warning,
+        ),
+        s => (format!("{} ({})", path, s.describe()), Style::Error),
+    }
+}
+
+fn print(rows: Rows) {
+    if term::color() {
+        print_colored(rows)
+    } else {
+        print_plain(rows)
+    }
+}
+
+fn print_colored(rows: Rows) {
+    let rows = rows
+        .iter()
+        .map(|r| Row::new(r.iter().map(|(s, style)| Cell::new(s, cell_format(*style))).collect()))
+        .collect();
     Table::new(rows, table_format())
         .print_stdout()
         .expect("error printing table")
 }
 
+/// Draws the same table as [`print_colored`] without any escape sequences
+fn print_plain(rows: Rows) {
+    let columns = rows.first().map_or(0, Vec::len);
+    let widths: Vec<usize> = (0..columns)
+        .map(|c| rows.iter().map(|r| r[c].0.chars().count()).max().unwrap_or(0))
+        .collect();
+    let line = |left: char, mid: char, right: char| {
+        let segments: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+        format!("{}{}{}", left, segments.join(&mid.to_string()), right)
+    };
+    println!("{}", line('┌', '┬', '┐'));
+    for (i, r) in rows.iter().enumerate() {
+        let cells: Vec<String> = r
+            .iter()
+            .zip(&widths)
+            .map(|((s, style), w)| match style {
+                Style::Head => format!(" {:^1$} ", s, w),
+                _ => format!(" {:<1$} ", s, w),
+            })
+            .collect();
+        println!("│{}│", cells.join("│"));
+        if i == 0 {
+            println!("{}", line('├', '┼', '┤'));
+        }
+    }
+    println!("{}", line('└', '┴', '┘'));
+}
+
+fn cell_format(style: Style) -> CellFormat {
+    match style {
+        Style::Plain => Default::default(),
+        Style::Head => head_format(),
+        Style::Group => CellFormat::builder().bold(true).build(),
+        Style::Warning => status_format(Color::Yellow),
+        Style::Error => status_format(Color::Red),
+    }
+}
+
+fn status_format(color: Color) -> CellFormat {
+    CellFormat::builder()
+        .foreground_color(Some(color))
+        .build()
+}
+
 fn head_format() -> CellFormat {
     CellFormat::builder()
         .bold(true)
@@ -92,3 +252,52 @@ fn seperator_style() -> Separator {
         .title(Some(HorizontalLine::new('├', '┤', '┼', '─')))
         .build()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn list(waypoints: &[(&str, Option<&str>)]) -> List {
+        List(waypoints.iter().map(|(n, g)| Waypoint::new(n, Path::new("/"), *g)).collect())
+    }
+
+    fn names(tree: &Tree, indent: &str) -> Vec<String> {
+        let mut rows = Vec::new();
+        tree.rows(&mut rows, indent, true);
+        rows.into_iter().map(|r| r[0].0.clone()).collect()
+    }
+
+    #[test]
+    fn nested_groups_become_branches() {
+        let list = list(&[
+            ("a", None),
+            ("b", Some("work")),
+            ("c", Some("work/clientA")),
+            ("d", Some("work/clientB")),
+            ("e", Some("work-b")),
+        ]);
+        let tree = Tree::new(&list, None);
+        assert_eq!(
+            names(&tree, ""),
+            [
+                "a",
+                "work/",
+                "├─ b",
+                "├─ clientA/",
+                "│  └─ c",
+                "└─ clientB/",
+                "   └─ d",
+                "work-b/",
+                "└─ e",
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_are_relative_to_the_root() {
+        let list = list(&[("b", Some("work")), ("c", Some("work/clientA")), ("e", Some("work-b"))]);
+        let tree = Tree::new(&list, Some("work/"));
+        assert_eq!(names(&tree, ""), ["b", "clientA/", "└─ c", "work-b/", "└─ e"]);
+    }
+}
@@ -0,0 +1,46 @@
+use clap::AppSettings;
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tele::TELE;
+
+/// Returns true if stdout is a terminal
+///
+/// The shell integration captures tele's output to `cd` into it, so it
+/// passes on whether its own stdout is a terminal through `TELE_STDOUT_TTY`.
+pub fn stdout_is_tty() -> bool {
+    match env::var("TELE_STDOUT_TTY").as_deref() {
+        Ok("1") => true,
+        Ok("0") => false,
+        _ => atty::is(atty::Stream::Stdout),
+    }
+}
+
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Returns true if output may contain color, see [`init_color`]
+pub fn color() -> bool {
+    COLOR.load(Ordering::Relaxed)
+}
+
+/// Decides whether to use color for help messages and tables
+///
+/// In order of precedence: `--color`, the `color` config key, `NO_COLOR`,
+/// then whether stdout is a terminal.
+pub fn init_color(flag: Option<&str>) {
+    let choice = match flag.map(str::to_string).or_else(|| TELE.config().get("color")) {
+        Some(c) if c == "always" => true,
+        Some(c) if c == "never" => false,
+        _ if matches!(env::var_os("NO_COLOR"), Some(v) if !v.is_empty()) => false,
+        _ => stdout_is_tty(),
+    };
+    COLOR.store(choice, Ordering::Relaxed)
+}
+
+/// clap setting matching [`color`]
+pub fn clap_color() -> AppSettings {
+    if color() {
+        AppSettings::ColorAlways
+    } else {
+        AppSettings::ColorNever
+    }
+}
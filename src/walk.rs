@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Walks the directories below `root` breadth-first, up to `max_depth` levels
+///
+/// `visit` is called for every directory and returns whether to descend
+/// into it. Hidden directories and symlinks are skipped, as are
+/// directories which cannot be read.
+pub fn walk_dirs<F: FnMut(&Path) -> bool>(root: &Path, max_depth: usize, mut visit: F) {
+    let mut queue = VecDeque::new();
+    queue.push_back((root.to_path_buf(), 0));
+    while let Some((dir, depth)) = queue.pop_front() {
+        if depth >= max_depth {
+            continue;
+        }
+        let entries = match fs::read_dir(&dir) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        let mut children: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+            .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+            .map(|e| e.path())
+            .collect();
+        children.sort();
+        for child in children {
+            if visit(&child) {
+                queue.push_back((child, depth + 1));
+            }
+        }
+    }
+}
+
+/// Directories below `root` named `name`, e.g. to locate a moved directory
+pub fn find_named(root: &Path, name: &OsStr, max_depth: usize) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    walk_dirs(root, max_depth, |dir| {
+        if dir.file_name() == Some(name) {
+            found.push(dir.to_path_buf());
+        }
+        true
+    });
+    found
+}
+
+/// Files or directories marking the root of a project
+pub const PROJECT_MARKERS: &[&str] = &[
+    ".git",
+    ".hg",
+    ".svn",
+    "Cargo.toml",
+    "package.json",
+    "go.mod",
+    "pyproject.toml",
+    "setup.py",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "Gemfile",
+    "composer.json",
+    "mix.exs",
+    "CMakeLists.txt",
+];
+
+/// Project roots at or below `root`, see [`PROJECT_MARKERS`]
+///
+/// Projects nested inside a project root are not reported, so a `root`
+/// which is itself a project is the only one found. A `max_depth` of 0
+/// only checks `root`.
+pub fn find_projects(root: &Path, max_depth: usize) -> Vec<PathBuf> {
+    if is_project(root) {
+        return vec![root.to_path_buf()];
+    }
+    let mut found = Vec::new();
+    walk_dirs(root, max_depth, |dir| {
+        if is_project(dir) {
+            found.push(dir.to_path_buf());
+            false
+        } else {
+            true
+        }
+    });
+    found
+}
+
+fn is_project(dir: &Path) -> bool {
+    PROJECT_MARKERS.iter().any(|m| dir.join(m).exists())
+}
+
+/// Directories matching a glob `pattern`
+///
+/// Each path component may use `*`, `?` and `[...]` (`[!...]` to negate).
+/// Hidden directories only match components starting with `.`.
+pub fn glob_dirs(pattern: &Path) -> Vec<PathBuf> {
+    let mut found = vec![PathBuf::new()];
+    for component in pattern.iter() {
+        let part = component.to_string_lossy();
+        if !part.contains(&['*', '?', '['][..]) {
+            found = found.into_iter().map(|dir| dir.join(component)).collect();
+            continue;
+        }
+        let mut next = Vec::new();
+        for dir in &found {
+            let read_from = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+            let entries = match fs::read_dir(read_from) {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+            let mut matched: Vec<PathBuf> = entries
+                .filter_map(Result::ok)
+                .filter(|e| {
+                    let name = e.file_name().to_string_lossy().into_owned();
+                    (part.starts_with('.') || !name.starts_with('.')) && glob_match(&part, &name)
+                })
+                .map(|e| dir.join(e.file_name()))
+                .collect();
+            matched.sort();
+            next.append(&mut matched);
+        }
+        found = next;
+    }
+    found.retain(|p| !p.as_os_str().is_empty() && p.is_dir());
+    found
+}
+
+/// Matches `name` against a single glob component
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    match_from(&p, &n)
+}
+
+fn match_from(p: &[char], n: &[char]) -> bool {
+    match p.first() {
+        None => n.is_empty(),
+        Some('*') => (0..=n.len()).any(|i| match_from(&p[1..], &n[i..])),
+        Some('?') => !n.is_empty() && match_from(&p[1..], &n[1..]),
+        Some('[') => match (p.iter().position(|&c| c == ']'), n.first()) {
+            (Some(end), Some(&c)) if end > 1 => {
+                let (negate, set) = match p[1] {
+                    '!' | '^' => (true, &p[2..end]),
+                    _ => (false, &p[1..end]),
+                };
+                let mut hit = false;
+                let mut i = 0;
+                while i < set.len() {
+                    if i + 2 < set.len() && set[i + 1] == '-' {
+                        hit |= set[i] <= c && c <= set[i + 2];
+                        i += 3;
+                    } else {
+                        hit |= set[i] == c;
+                        i += 1;
+                    }
+                }
+                hit != negate && match_from(&p[end + 1..], &n[1..])
+            }
+            // an unclosed `[` matches itself
+            _ => n.first() == Some(&'[') && match_from(&p[1..], &n[1..]),
+        },
+        Some(c) => n.first() == Some(c) && match_from(&p[1..], &n[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn wildcards_match_any_characters() {
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("src*", "src"));
+        assert!(glob_match("*-web", "app-web"));
+        assert!(!glob_match("*-web", "app-webs"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(!glob_match("a?c", "ac"));
+    }
+
+    #[test]
+    fn brackets_match_sets_and_ranges() {
+        assert!(glob_match("[abc]x", "bx"));
+        assert!(!glob_match("[abc]x", "dx"));
+        assert!(glob_match("v[0-9]", "v7"));
+        assert!(!glob_match("v[0-9]", "vx"));
+        assert!(glob_match("[!a-c]", "d"));
+        assert!(!glob_match("[!a-c]", "b"));
+        assert!(glob_match("[^a]", "b"));
+    }
+
+    #[test]
+    fn an_unclosed_bracket_matches_itself() {
+        assert!(glob_match("a[b", "a[b"));
+        assert!(!glob_match("a[b", "ab"));
+    }
+
+    #[test]
+    fn the_scan_root_can_be_a_project() {
+        let root = env::temp_dir().join(format!("tele-projects-{}", std::process::id()));
+        fs::create_dir_all(root.join("sub/inner")).unwrap();
+        fs::write(root.join("sub/Cargo.toml"), "").unwrap();
+        fs::write(root.join("sub/inner/go.mod"), "").unwrap();
+
+        assert_eq!(find_projects(&root, 0), Vec::<PathBuf>::new());
+        assert_eq!(find_projects(&root, 1), vec![root.join("sub")]);
+        assert_eq!(find_projects(&root.join("sub"), 0), vec![root.join("sub")]);
+        assert_eq!(find_projects(&root.join("sub"), 3), vec![root.join("sub")]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn hidden_directories_need_a_leading_dot() {
+        let root = env::temp_dir().join(format!("tele-glob-{}", std::process::id()));
+        for dir in &["alpha", "beta", ".hidden"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(root.join("afile"), "").unwrap();
+
+        let found = glob_dirs(&root.join("*"));
+        assert_eq!(found, vec![root.join("alpha"), root.join("beta")]);
+        assert_eq!(glob_dirs(&root.join(".*")), vec![root.join(".hidden")]);
+        assert_eq!(glob_dirs(&root.join("a*")), vec![root.join("alpha")]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
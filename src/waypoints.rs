@@ -1,59 +1,233 @@
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::fs;
-use crate::filesystem::Filesystem;
-use crate::TELE;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::error::{Error, Result};
+use crate::paths;
 
-type Outcome<T> = Result<T, ()>;
-const INVALID_WP_NAME: &'static str = "is not a waypoint";
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Waypoint {
     pub name: String,
-    pub path: String,
+    #[serde(with = "crate::paths::lossless")]
+    pub path: PathBuf,
     pub group: Option<String>,
+    /// Number of successful teleports
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub visits: u64,
+    /// Unix timestamp of the last teleport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_visit: Option<u64>,
 }
 
 impl Waypoint {
-    pub fn new(name: &str, group: Option<&str>) -> Self {
+    pub fn new(name: &str, path: &Path, group: Option<&str>) -> Self {
         Self {
             name: name.to_string(),
-            path: Filesystem::current_dir(),
-            group: group.map(str::to_string),
+            path: path.to_path_buf(),
+            group: group.and_then(group_path),
+            visits: 0,
+            last_visit: None,
         }
     }
     pub fn rename(&self, name: &str) -> Self {
         Self {
             name: String::from(name),
-            path: self.path.clone(),
-            group: self.group.clone(),
+            ..self.clone()
         }
     }
-    pub fn repath(&self, path: &str) -> Self {
+    pub fn repath(&self, path: &Path) -> Self {
         Self {
-            name: self.name.clone(),
-            path: String::from(path),
-            group: self.group.clone(),
+            path: path.to_path_buf(),
+            ..self.clone()
         }
     }
     pub fn regroup(&self, group: &str) -> Self {
         Self {
-            name: self.name.clone(),
-            path: self.path.clone(),
-            group: Some(String::from(group)),
+            group: group_path(group),
+            ..self.clone()
         }
     }
     pub fn ungroup(&self) -> Self {
         Self {
-            name: self.name.clone(),
-            path: self.path.clone(),
             group: None,
+            ..self.clone()
+        }
+    }
+    /// Removes `group` from the waypoint's group path, moving it one level up
+    pub fn dissolve(&self, group: &str) -> Self {
+        let group = group.trim_matches('/');
+        let rest = match &self.group {
+            Some(g) if g == group => "",
+            Some(g) => match g.strip_prefix(group).and_then(|r| r.strip_prefix('/')) {
+                Some(rest) => rest,
+                None => return self.clone(),
+            },
+            None => return self.clone(),
+        };
+        let parent = group.rsplit_once('/').map_or("", |(parent, _)| parent);
+        Self {
+            group: group_path(&format!("{}/{}", parent, rest)),
+            ..self.clone()
+        }
+    }
+    pub fn visit(&self) -> Self {
+        Self {
+            visits: self.visits + 1,
+            last_visit: Some(unix_now()),
+            ..self.clone()
         }
     }
+
+    /// Visit count weighted by how recently the waypoint was visited
+    pub fn frecency(&self) -> f64 {
+        let age = match self.last_visit {
+            Some(t) => unix_now().saturating_sub(t),
+            None => return 0.0,
+        };
+        let weight = match age {
+            a if a < HOUR => 4.0,
+            a if a < DAY => 2.0,
+            a if a < WEEK => 0.5,
+            _ => 0.25,
+        };
+        self.visits as f64 * weight
+    }
+
+    /// Returns true if the waypoint is in `group` or one of its subgroups
+    pub fn in_group(&self, group: &str) -> bool {
+        let group = group.trim_matches('/');
+        match &self.group {
+            Some(g) => g == group || matches!(g.strip_prefix(group), Some(r) if r.starts_with('/')),
+            None => false,
+        }
+    }
+
+    /// The stored path with `~` and environment variables expanded
+    pub fn expanded_path(&self) -> PathBuf {
+        paths::expand(&self.path)
+    }
+
+    /// Checks the waypoint's directory on the filesystem
+    pub fn status(&self) -> PathStatus {
+        let path = &self.expanded_path();
+        match fs::metadata(path) {
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => PathStatus::Unreadable,
+            Err(_) => PathStatus::Missing,
+            Ok(m) if !m.is_dir() => PathStatus::NotADirectory,
+            Ok(_) => match fs::read_dir(path) {
+                Err(e) if e.kind() == ErrorKind::PermissionDenied => PathStatus::Unreadable,
+                _ if is_symlink(path) => PathStatus::Symlink,
+                _ => PathStatus::Ok,
+            },
+        }
+    }
+}
+
+/// Normalizes a group path such as `work//clientA/`, `None` if it is empty
+///
+/// Groups nest with `/`, so `work/clientA` is a subgroup of `work`.
+pub fn group_path(group: &str) -> Option<String> {
+    let segments: Vec<&str> = group.split('/').filter(|s| !s.is_empty()).collect();
+    if segments.is_empty() {
+        None
+    } else {
+        Some(segments.join("/"))
+    }
+}
+
+/// Compared segment by segment so `work/a` sorts before `work-b`
+fn group_segments(w: &Waypoint) -> Option<Vec<&str>> {
+    w.group.as_ref().map(|g| g.split('/').collect())
 }
 
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+/// State of a waypoint's directory, see [`Waypoint::status`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathStatus {
+    Ok,
+    /// A symlink to a directory elsewhere
+    Symlink,
+    Missing,
+    NotADirectory,
+    Unreadable,
+}
+
+impl PathStatus {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Symlink => "symlink",
+            Self::Missing => "missing",
+            Self::NotADirectory => "not a directory",
+            Self::Unreadable => "unreadable",
+        }
+    }
+
+    /// Returns true if teleporting to the waypoint would fail
+    pub fn is_problem(self) -> bool {
+        matches!(self, Self::Missing | Self::NotADirectory | Self::Unreadable)
+    }
+}
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+
+/// Names taken by tele's subcommands
+///
+/// Waypoints named like this from older versions can still be reached with
+/// `tele -- <name>`.
+pub const RESERVED_NAMES: &[&str] = &[
+    "add", "rm", "list", "init", "doctor", "back", "forward", "history", "scan", "import", "export",
+    "undo", "redo", "log", "complete", "help", "-",
+];
+
+/// Checks that `name` can be typed as `tele <name>`
+pub fn validate_name(name: &str) -> Result<()> {
+    let reason = if name.trim().is_empty() {
+        "names cannot be empty"
+    } else if name.starts_with('-') && name != "-" {
+        "names cannot start with '-'"
+    } else if name.contains('/') {
+        "names cannot contain '/'"
+    } else if name.chars().any(char::is_control) {
+        "names cannot contain control characters"
+    } else if RESERVED_NAMES.contains(&name) {
+        "it is reserved for a subcommand"
+    } else {
+        return Ok(());
+    };
+    Err(Error::InvalidName {
+        name: name.to_string(),
+        reason,
+    })
+}
 
 /// List
-#[derive(Serialize, Deserialize, Clone, Debug)]
+///
+/// Mutating methods validate their input first and leave the list untouched
+/// on error. On success they return the affected waypoints as they were
+/// before the change.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct List(pub Vec<Waypoint>);
 
 impl List {
@@ -63,17 +237,58 @@ impl List {
     }
 
     fn get_group(&self, group: &str) -> Option<&Waypoint> {
-        self.0.iter().find(|w| w.group == Some(group.to_string()))
+        self.0.iter().find(|w| w.in_group(group))
+    }
+
+    /// Returns every defined group including parents of nested ones, sorted
+    /// and deduplicated
+    pub fn groups(&self) -> Vec<String> {
+        let mut groups: Vec<String> = Vec::new();
+        for g in self.0.iter().filter_map(|w| w.group.as_deref()) {
+            let ends = g.match_indices('/').map(|(i, _)| i).chain(Some(g.len()));
+            groups.extend(ends.map(|end| g[..end].to_string()));
+        }
+        groups.sort();
+        groups.dedup();
+        groups
     }
 
-    fn get_index(&self, name: &str) -> Option<usize> {
-        self.0.iter().position(|w| w.name == name)
+    /// Waypoints sharing the same directory, grouped by path
+    pub fn duplicates(&self) -> Vec<Vec<&Waypoint>> {
+        let mut by_path: Vec<(PathBuf, Vec<&Waypoint>)> = Vec::new();
+        for w in &self.0 {
+            let path = w.expanded_path();
+            match by_path.iter_mut().find(|(p, _)| *p == path) {
+                Some((_, ws)) => ws.push(w),
+                None => by_path.push((path, vec![w])),
+            }
+        }
+        by_path
+            .into_iter()
+            .map(|(_, ws)| ws)
+            .filter(|ws| ws.len() > 1)
+            .collect()
+    }
+
+    fn get_index(&self, name: &str) -> Result<usize> {
+        self.0
+            .iter()
+            .position(|w| w.name == name)
+            .ok_or_else(|| Error::NotFound(name.to_string()))
     }
 
     // filter
-    fn filter_group(&self, group: Option<&str>) -> Option<Self> {
-        let g = group.map(str::to_string);
-        let filtered_wps: Vec<Waypoint> = self.0.iter().filter(|w| w.group == g).cloned().collect();
+    /// Waypoints in `group` and its subgroups, or without a group for `None`
+    pub fn filter_group(&self, group: Option<&str>) -> Option<Self> {
+        let filtered_wps: Vec<Waypoint> = self
+            .0
+            .iter()
+            .filter(|w| match group {
+                Some(g) => w.in_group(g),
+                None => w.group.is_none(),
+            })
+            .cloned()
+            .collect();
         if !filtered_wps.is_empty() {
             Some(Self(filtered_wps))
         } else {
@@ -81,226 +296,262 @@ impl List {
         }
     }
 
+    /// Waypoints shown by `view`
+    pub fn filter_view(&self, view: &ListView) -> Option<Self> {
+        match view {
+            ListView::All if self.0.is_empty() => None,
+            ListView::All => Some(self.clone()),
+            ListView::Group(g) => self.filter_group(Some(g)),
+            ListView::Groupless => self.filter_group(None),
+        }
+    }
+
+    // create
+    pub fn add_entry(&mut self, waypoint: Waypoint) -> Result<()> {
+        validate_name(&waypoint.name)?;
+        self.check_vacant(&waypoint.name)?;
+        self.0.push(waypoint);
+        Ok(())
+    }
+
     // delete
-    pub fn remove_entries(mut self, names: Vec<&str>) -> Outcome<Self> {
+    pub fn remove_entries(&mut self, names: &[&str]) -> Result<Vec<Waypoint>> {
         for n in names {
-            let mut i = 0;
-            while i != self.0.len() {
-                if self.0.get(i).unwrap().name == n.to_string() {
-                    let n = &self.0.get(i).unwrap().name.clone();
-                    self.0.remove(i);
-                    println!("'{}' removed from waypoints", &n)
-                } else {
-                    i += 1;
-                }
-            }
+            self.get_index(n)?;
         }
-        Ok(self)
-    }
-
-    pub fn remove_group(mut self, groups: Vec<&str>) -> Outcome<Self> {
-        for g in groups {
-            if self.get_group(g).is_some() {
-                let mut i = 0;
-                while i != self.0.len() {
-                    if self.0.get(i).unwrap().group == Some(g.to_string()) {
-                        let n = &self.0.get(i).unwrap().name.clone();
-                        self.0.remove(i);
-                        println!("'{}' removed from waypoints", &n)
-                    } else {
-                        i += 1;
-                    }
-                }
-                println!("group '{}' removed", &g)
+        Ok(self.drain_where(|w| names.contains(&w.name.as_str())))
+    }
+
+    /// Removes the waypoints in `groups` and their subgroups
+    pub fn remove_group(&mut self, groups: &[&str]) -> Result<Vec<Waypoint>> {
+        self.check_groups(groups)?;
+        Ok(self.drain_where(|w| groups.iter().any(|g| w.in_group(g))))
+    }
 
+    fn drain_where<F: Fn(&Waypoint) -> bool>(&mut self, f: F) -> Vec<Waypoint> {
+        let mut removed = Vec::new();
+        let mut i = 0;
+        while i != self.0.len() {
+            if f(&self.0[i]) {
+                removed.push(self.0.remove(i));
             } else {
-                println!("group '{}' is not defined", &g)
+                i += 1;
             }
         }
-        Ok(self)
+        removed
     }
 
     // update
-    pub fn rename_entry(mut self, name: &str, new_name: &str) -> Outcome<Self> {
-        match self.get_index(name) {
-            Some(i) => {
-                let new_wp = self.0.get(i).unwrap().clone().rename(new_name);
-                self.0.push(new_wp);
-                self.0.remove(i);
-                println!("'{}' renamed to '{}'", name, new_name);
-                Ok(self)
-            }
-            None => Err(println!("'{}' {}", name, INVALID_WP_NAME)),
-        }
+    pub fn rename_entry(&mut self, name: &str, new_name: &str) -> Result<Waypoint> {
+        let i = self.get_index(name)?;
+        validate_name(new_name)?;
+        self.check_vacant(new_name)?;
+        let new_wp = self.0[i].rename(new_name);
+        Ok(std::mem::replace(&mut self.0[i], new_wp))
     }
 
-    pub fn repath_entry(mut self, name: &str, path: &str) -> Outcome<Self> {
-        match self.get_index(name) {
-            Some(i) => {
-                let old_path = self.0.get(i).unwrap().path.clone();
-                let new_wp = self.0.get(i).unwrap().clone().repath(path);
-                self.0.remove(i);
-                self.0.push(new_wp);
-                println!(
-                        "path changed for waypoint '{}':\n old: {}\n new: {}",
-                        name, old_path, path
-                    );
-                Ok(self)
-            }
-            None => Err(println!("'{}' {}", name, INVALID_WP_NAME)),
-        }
+    pub fn repath_entry(&mut self, name: &str, path: &Path) -> Result<Waypoint> {
+        let i = self.get_index(name)?;
+        let new_wp = self.0[i].repath(path);
+        Ok(std::mem::replace(&mut self.0[i], new_wp))
     }
 
-    pub fn regroup_entry(mut self, name: &str, group: &str) -> Outcome<Self> {
-        match self.get_index(name) {
-            Some(i) => {
-                if let Some(old_group) = self.0.get(i).unwrap().group.clone() {
-                    let new_wp = self.0.get(i).unwrap().clone().regroup(group);
-                    self.0.remove(i);
-                    self.0.push(new_wp);
-                    println!(
-                        "'{}' has been regrouped from '{}' to '{}'",
-                        name, old_group, group
-                    );
-                    Ok(self)
-                } else {
-                    let new_wp = self.0.get(i).unwrap().clone()
-                        .regroup(group);
-                    self.0.remove(i);
-                    self.0.push(new_wp);
-                    println!("'{}' has been added to group '{}'", name, group);
-                    Ok(self)
-                }
-            }
-            None => Err(println!("'{}' {}", name, INVALID_WP_NAME)),
+    /// Moves a waypoint to `group`, which must name at least one level
+    pub fn regroup_entry(&mut self, name: &str, group: &str) -> Result<Waypoint> {
+        let i = self.get_index(name)?;
+        if group_path(group).is_none() {
+            return Err(Error::InvalidGroup(group.to_string()));
         }
+        let new_wp = self.0[i].regroup(group);
+        Ok(std::mem::replace(&mut self.0[i], new_wp))
     }
 
-    pub fn ungroup_entry(mut self, name: &str) -> Outcome<Self> {
-        match self.get_index(name) {
-            Some(i) => {
-                if let Some(old_group) = self.0.get(i).unwrap().clone().group {
-                    let new_wp = self.0.get(i).unwrap().clone().ungroup();
-                    self.0.remove(i);
-                    self.0.push(new_wp);
-                    println!("'{}' has been removed from group '{}'", name, old_group);
-                    Ok(self)
-                } else {
-                    Err(println!("'{}' does not have a group", name))
-                }
-            }
-            None => Err(println!("'{}' {}", name, INVALID_WP_NAME)),
+    pub fn ungroup_entry(&mut self, name: &str) -> Result<Waypoint> {
+        let i = self.get_index(name)?;
+        if self.0[i].group.is_none() {
+            return Err(Error::NoGroup(name.to_string()));
         }
+        let new_wp = self.0[i].ungroup();
+        Ok(std::mem::replace(&mut self.0[i], new_wp))
     }
 
-    pub fn dissolve_groups(mut self, groups: Vec<&str>) -> Outcome<Self> {
-        for g in groups {
-            if self.get_group(g).is_some() {
-                let mut i = 0;
-                while i != self.0.len() {
-                    if self.0.get(i).unwrap().group == Some(g.to_string()) {
-                        let n = &self.0.get(i).unwrap().name.clone();
-                        let new_wp = self.0.get(i).unwrap().clone().ungroup();
-                        self.0.remove(i);
-                        self.0.push(new_wp);
-                        println!("'{}' ungrouped", &n)
-                    } else {
-                        i += 1;
-                    }
-                }
-                println!("group '{}' has been dissolved", &g)
-            } else {
-                println!("successfully dissolved groups")
-            }
-        }
-        Ok(self)
+    pub fn visit_entry(&mut self, name: &str) -> Result<Waypoint> {
+        let i = self.get_index(name)?;
+        let new_wp = self.0[i].visit();
+        Ok(std::mem::replace(&mut self.0[i], new_wp))
     }
 
-    /// Load `waypoints.json`
-    pub fn load() -> List {
-        // create file if it does not exist
-        if fs::metadata(Filesystem::waypoints_file()).is_err() {
-            fs::create_dir_all(TELE.config_dir())
-                .expect("could note create directory '~/.config/tele'");
-            fs::write(Filesystem::waypoints_file(), b"[]")
-                .expect("could not create file '~/.config/tele/waypoints.json'")
+    /// Moves the waypoints and subgroups of `groups` one level up
+    ///
+    /// Dissolving `work/clientA` moves `work/clientA/backend` to
+    /// `work/backend`; dissolving a top-level group ungroups its waypoints.
+    pub fn dissolve_groups(&mut self, groups: &[&str]) -> Result<Vec<Waypoint>> {
+        self.check_groups(groups)?;
+        let mut dissolved = Vec::new();
+        for w in self.0.iter_mut() {
+            let new_wp = groups.iter().fold(w.clone(), |w, g| w.dissolve(g));
+            if new_wp != *w {
+                dissolved.push(std::mem::replace(w, new_wp));
+            }
         }
-        // read file
-        let file_string = fs::read_to_string(Filesystem::waypoints_file())
-            .expect("error opening waypoint list");
-        // deserialize
-        serde_json::from_str(&file_string).expect("error deserializing list")
-    }
-
-    /// Load specific group
-    pub fn load_group(group: &str) -> Option<List> {
-        let list = Self::load();
-        list.filter_group(Some(group))
+        Ok(dissolved)
     }
 
-    /// Load waypoints without a group
-    pub fn load_groupless() -> Option<List> {
-        let list = Self::load();
-        list.filter_group(None)
+    /// Errors if `name` is already taken
+    fn check_vacant(&self, name: &str) -> Result<()> {
+        match self.get_entry(name) {
+            Some(w) => Err(Error::AlreadyExists {
+                name: w.name.clone(),
+                path: w.path.clone(),
+            }),
+            None => Ok(()),
+        }
     }
 
-    /// Sorts and writes List to `waypoints.json`
-    pub fn save(self) {
-        let l = self.sort().clone();
-        let json = serde_json::to_string_pretty(&l).expect("could not serialize input");
-        fs::write(Filesystem::waypoints_file(), json).expect("unable to write list");
+    /// Errors on the first group which is not defined
+    fn check_groups(&self, groups: &[&str]) -> Result<()> {
+        match groups.iter().find(|g| self.get_group(g).is_none()) {
+            Some(g) => Err(Error::GroupNotFound(g.to_string())),
+            None => Ok(()),
+        }
     }
 
-    /// Sorts waypoints
-    fn sort(mut self) -> Self {
-        match ListSort::from_config() {
-            ListSort::Name => {
-                self.0.sort_by(|a, b| a.name.cmp(&b.name));
-                self.0.sort_by(|a, b| a.group.cmp(&b.group));
-                self
-            }
-            _ =>{
-                self.0.sort_by(|a, b| a.path.cmp(&b.path));
-                self.0.sort_by(|a, b| a.group.cmp(&b.group));
-                self
-            }
+    /// Sorts waypoints within their groups, subgroups following their parent
+    pub fn sort_by(mut self, sort: ListSort) -> Self {
+        match sort {
+            ListSort::Name => self.0.sort_by(|a, b| a.name.cmp(&b.name)),
+            ListSort::Path => self.0.sort_by(|a, b| a.path.cmp(&b.path)),
+            ListSort::Frecency => self
+                .0
+                .sort_by(|a, b| b.frecency().partial_cmp(&a.frecency()).unwrap_or(Ordering::Equal)),
         }
+        self.0.sort_by(|a, b| group_segments(a).cmp(&group_segments(b)));
+        self
     }
 }
 
+#[derive(Clone, Copy, Debug)]
 pub enum ListSort {
     Path,
     Name,
+    Frecency,
 }
 impl ListSort {
-    pub fn from_config() -> Self {
-        if let Some(s) = &TELE.config().get("default-sort") {
-            match s.as_str() {
-                "path" => Self::Path,
-                "name" => Self::Name,
-                _ => Self::Path,
-            }
-        } else {
-            Self::Path
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "path" => Self::Path,
+            "name" => Self::Name,
+            "frecency" => Self::Frecency,
+            _ => Self::Path,
         }
     }
 }
 
+#[derive(Clone, Debug)]
 pub enum ListView {
     Groupless,
     All,
     Group(String),
 }
 impl ListView {
-    pub fn from_config() -> Self {
-        if let Some(s) = &TELE.config().get("default-view") {
-            match s.as_str() {
-                "ungrouped" => Self::Groupless,
-                "all" => Self::All,
-                _ => Self::Groupless,
-            }
-        } else {
-            Self::Groupless
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "ungrouped" => Self::Groupless,
+            "all" => Self::All,
+            _ => Self::Groupless,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn waypoint(name: &str, group: Option<&str>) -> Waypoint {
+        Waypoint::new(name, Path::new("/"), group)
+    }
+
+    fn groups(list: &List) -> Vec<Option<&str>> {
+        list.0.iter().map(|w| w.group.as_deref()).collect()
+    }
+
+    #[test]
+    fn group_paths_are_normalized() {
+        assert_eq!(group_path("/work//clientA/").as_deref(), Some("work/clientA"));
+        assert_eq!(group_path("//"), None);
+        assert_eq!(group_path(""), None);
+    }
+
+    #[test]
+    fn in_group_matches_whole_segments() {
+        let w = waypoint("a", Some("work/clientA"));
+        assert!(w.in_group("work"));
+        assert!(w.in_group("work/"));
+        assert!(w.in_group("work/clientA"));
+        assert!(!w.in_group("wor"));
+        assert!(!w.in_group("work/client"));
+        assert!(!waypoint("b", Some("work-b")).in_group("work"));
+        assert!(!waypoint("c", None).in_group("work"));
+    }
+
+    #[test]
+    fn dissolving_a_middle_level() {
+        let w = waypoint("a", Some("work/clientA/backend"));
+        assert_eq!(w.dissolve("work/clientA").group.as_deref(), Some("work/backend"));
+        assert_eq!(w.dissolve("work/clientA/backend").group.as_deref(), Some("work/clientA"));
+        let b = waypoint("b", Some("work/clientA"));
+        assert_eq!(b.dissolve("work/clientA").group.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn dissolving_a_top_level_group() {
+        assert_eq!(waypoint("a", Some("work")).dissolve("work").group, None);
+        assert_eq!(waypoint("b", Some("work/x")).dissolve("work").group.as_deref(), Some("x"));
+        assert_eq!(waypoint("c", Some("work-b")).dissolve("work").group.as_deref(), Some("work-b"));
+    }
+
+    #[test]
+    fn dissolving_groups_in_a_list() {
+        let mut list = List(vec![
+            waypoint("a", Some("work/clientA")),
+            waypoint("b", Some("work-b")),
+            waypoint("c", Some("work")),
+        ]);
+        let dissolved = list.dissolve_groups(&["work"]).unwrap();
+        assert_eq!(dissolved.len(), 2);
+        assert_eq!(groups(&list), [Some("clientA"), Some("work-b"), None]);
+        assert!(list.dissolve_groups(&["work"]).is_err());
+    }
+
+    #[test]
+    fn groups_include_parents() {
+        let list = List(vec![
+            waypoint("a", Some("work/clientA/backend")),
+            waypoint("b", Some("work-b")),
+            waypoint("c", Some("work")),
+            waypoint("d", None),
+        ]);
+        assert_eq!(list.groups(), ["work", "work-b", "work/clientA", "work/clientA/backend"]);
+    }
+
+    #[test]
+    fn subgroups_sort_after_their_parent() {
+        let list = List(vec![
+            waypoint("a", Some("work-b")),
+            waypoint("b", Some("work/clientA")),
+            waypoint("c", None),
+            waypoint("d", Some("work")),
+        ]);
+        let sorted = list.sort_by(ListSort::Name);
+        assert_eq!(groups(&sorted), [None, Some("work"), Some("work/clientA"), Some("work-b")]);
+    }
+
+    #[test]
+    fn regrouping_needs_a_group_name() {
+        let mut list = List(vec![waypoint("a", Some("old"))]);
+        assert!(matches!(list.regroup_entry("a", "/"), Err(Error::InvalidGroup(_))));
+        assert_eq!(groups(&list), [Some("old")]);
+        list.regroup_entry("a", "a//b/").unwrap();
+        assert_eq!(groups(&list), [Some("a/b")]);
+    }
+}
@@ -1,8 +1,12 @@
 #!/bin/sh
-function t() {
-  OUTPUT=`tele $@`
-  if [ $? -eq 2 ]
-    then cd "$OUTPUT"
-    else echo "$OUTPUT"
+t() {
+  if [ -t 1 ]; then __tele_tty=1; else __tele_tty=0; fi
+  __tele_output="$(TELE_STDOUT_TTY=$__tele_tty command tele "$@")"
+  __tele_status=$?
+  if [ "$__tele_status" -eq 2 ]; then
+    cd -- "$__tele_output" || return
+  else
+    [ -n "$__tele_output" ] && printf '%s\n' "$__tele_output"
+    return "$__tele_status"
   fi
 }